@@ -0,0 +1,66 @@
+//! Due date buckets the agenda grouping puts tasks in
+
+use crate::dates;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Bucket {
+    Overdue,
+    Today,
+    Tomorrow,
+    /// After tomorrow, up to sunday
+    ThisWeek,
+    Later,
+    NoDate,
+    /// Only shows up when done tasks aren't hidden
+    Done,
+}
+impl Bucket {
+    /// In the order the agenda lists them
+    pub const ALL : [Bucket; 7] = [
+        Bucket::Overdue,
+        Bucket::Today,
+        Bucket::Tomorrow,
+        Bucket::ThisWeek,
+        Bucket::Later,
+        Bucket::NoDate,
+        Bucket::Done,
+    ];
+
+    /// `due` and `today` are in days since the epoch, `due` is `dates::NO_DUE` when there is no due date
+    pub fn of(due : i64, done : bool, today : i64) -> Bucket {
+        if done {
+            Bucket::Done
+        }
+        else if due == dates::NO_DUE {
+            Bucket::NoDate
+        }
+        else if due < today {
+            Bucket::Overdue
+        }
+        else if due == today {
+            Bucket::Today
+        }
+        else if due == today + 1 {
+            Bucket::Tomorrow
+        }
+        else if due <= dates::week_of(today).1 {
+            Bucket::ThisWeek
+        }
+        else {
+            Bucket::Later
+        }
+    }
+
+    /// Name of its group row
+    pub fn title(self) -> &'static str {
+        match self {
+            Bucket::Overdue => "Overdue",
+            Bucket::Today => "Today",
+            Bucket::Tomorrow => "Tomorrow",
+            Bucket::ThisWeek => "This Week",
+            Bucket::Later => "Later",
+            Bucket::NoDate => "No date",
+            Bucket::Done => "Done",
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Board of task cards in columns by category or priority, the `board` page of the `views` stack
+
+use crate::priority::{priority_class, MAX_PRIORITY};
+use crate::settings::BoardColumns;
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Width of every column, so a long name doesn't make its column take over
+const COLUMN_WIDTH : i32 = 200;
+
+/// A task as the board sees it
+pub struct BoardTask {
+    /// Index in `TasksManager::tasks`
+    pub index : usize,
+    pub name : String,
+    pub category : String,
+    pub sub_category : String,
+    /// Already formatted, empty without a due date
+    pub due : String,
+    pub priority : u32,
+    pub done : bool,
+}
+
+/// A column of the board, which is also what a task dropped on it becomes
+#[derive(Clone, PartialEq)]
+pub enum Column {
+    Category(String),
+    Priority(u32),
+    Done,
+}
+impl Column {
+    fn title(&self) -> String {
+        match self {
+            Column::Category(category) => category.clone(),
+            Column::Priority(priority) => format!("Priority {}", priority),
+            Column::Done => "Done".to_string(),
+        }
+    }
+
+    fn holds(&self, task : &BoardTask) -> bool {
+        match self {
+            Column::Category(category) => !task.done && task.category == *category,
+            Column::Priority(priority) => !task.done && task.priority == *priority,
+            Column::Done => task.done,
+        }
+    }
+}
+
+type Activated = Rc<dyn Fn(usize)>;
+type Dropped = Rc<dyn Fn(usize, &Column)>;
+
+#[derive(Clone)]
+pub struct BoardView {
+    combo : gtk::ComboBoxText,
+    columns_box : gtk::Box,
+    columns : Rc<Cell<BoardColumns>>,
+    /// What the last `show` got, kept around for switching what the columns are
+    tasks : Rc<RefCell<Vec<BoardTask>>>,
+    /// Cards and columns come and go with every redraw, so their handlers look these up
+    activated : Rc<RefCell<Option<Activated>>>,
+    dropped : Rc<RefCell<Option<Dropped>>>,
+}
+impl BoardView {
+    /// Fills `root` with the board, its columns being `columns` to begin with
+    pub fn new(root : &gtk::Box, columns : BoardColumns) -> BoardView {
+        let header = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        let combo = gtk::ComboBoxText::new();
+        combo.append(Some(BoardColumns::Category.as_str()), "Category");
+        combo.append(Some(BoardColumns::Priority.as_str()), "Priority");
+        combo.set_active_id(Some(columns.as_str()));
+        header.pack_start(&gtk::Label::new(Some("Columns by")), false, false, 0);
+        header.pack_start(&combo, false, false, 0);
+        root.pack_start(&header, false, false, 0);
+
+        let columns_box = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        let scroll = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+        scroll.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Never);
+        scroll.add(&columns_box);
+        root.pack_start(&scroll, true, true, 0);
+
+        let view = BoardView {
+            combo,
+            columns_box,
+            columns : Rc::new(Cell::new(columns)),
+            tasks : Rc::new(RefCell::new(Vec::new())),
+            activated : Rc::new(RefCell::new(None)),
+            dropped : Rc::new(RefCell::new(None)),
+        };
+
+        // Connect switching what the columns are
+        let clone = view.clone();
+        view.combo.connect_changed(move |combo| {
+            if let Some(columns) = combo.get_active_id().and_then(|id| BoardColumns::parse(&id)) {
+                clone.columns.set(columns);
+                clone.redraw();
+            }
+        });
+
+        view
+    }
+
+    /// Shows `tasks` from now on
+    pub fn show(&self, tasks : Vec<BoardTask>) {
+        *self.tasks.borrow_mut() = tasks;
+        self.redraw();
+    }
+
+    /// Calls `changed` whenever the columns are switched to something else
+    pub fn connect_columns_changed<F : Fn(BoardColumns) + 'static>(&self, changed : F) {
+        let columns = Rc::clone(&self.columns);
+        self.combo.connect_changed(move |_| changed(columns.get()));
+    }
+
+    /// Calls `activated` with the task's index when its card is clicked
+    pub fn connect_task_activated<F : Fn(usize) + 'static>(&self, activated : F) {
+        *self.activated.borrow_mut() = Some(Rc::new(activated));
+    }
+
+    /// Calls `dropped` with the task's index and the column when a card is dragged onto a column
+    pub fn connect_task_dropped<F : Fn(usize, &Column) + 'static>(&self, dropped : F) {
+        *self.dropped.borrow_mut() = Some(Rc::new(dropped));
+    }
+
+    fn redraw(&self) {
+        for child in self.columns_box.get_children() {
+            self.columns_box.remove(&child);
+        }
+
+        let tasks = self.tasks.borrow();
+        let mut columns = Vec::new();
+        match self.columns.get() {
+            BoardColumns::Category => {
+                for task in tasks.iter().filter(|t| !t.done) {
+                    let column = Column::Category(task.category.clone());
+                    if !columns.contains(&column) {
+                        columns.push(column);
+                    }
+                }
+            }
+            // Every priority, so there is somewhere to drag a card to
+            BoardColumns::Priority => columns.extend((0..=MAX_PRIORITY as u32).rev().map(Column::Priority)),
+        }
+        columns.push(Column::Done);
+
+        for column in columns.into_iter() {
+            let cards : Vec<&BoardTask> = tasks.iter().filter(|t| column.holds(t)).collect();
+            self.columns_box.pack_start(&self.column_widget(column, &cards), false, true, 0);
+        }
+        self.columns_box.show_all();
+    }
+
+    /// A column with its title and cards, which takes dropped cards
+    fn column_widget(&self, column : Column, cards : &[&BoardTask]) -> gtk::EventBox {
+        let inner = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        let title = gtk::Label::new(None);
+        title.set_markup(&format!("<b>{}</b> ({})", glib::markup_escape_text(&column.title()), cards.len()));
+        inner.pack_start(&title, false, false, 0);
+
+        let list = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        for task in cards.iter() {
+            list.pack_start(&self.card(task), false, false, 0);
+        }
+        let scroll = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+        scroll.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+        scroll.add(&list);
+        inner.pack_start(&scroll, true, true, 0);
+
+        let widget = gtk::EventBox::new();
+        widget.set_size_request(COLUMN_WIDTH, -1);
+        widget.get_style_context().add_class("board-column");
+        widget.add(&inner);
+
+        let targets = [gtk::TargetEntry::new(crate::TASK_TARGET, gtk::TargetFlags::SAME_APP, 0)];
+        widget.drag_dest_set(gtk::DestDefaults::ALL, &targets, gdk::DragAction::MOVE);
+        let dropped = Rc::clone(&self.dropped);
+        widget.connect_drag_data_received(move |_, _, _, _, data, _, _| {
+            let index = String::from_utf8(data.get_data()).ok().and_then(|s| s.parse::<usize>().ok());
+            let dropped = dropped.borrow().clone();
+            if let (Some(index), Some(dropped)) = (index, dropped) {
+                // Changing the task redraws the board, the dragged card included,
+                // which is better left until the drag is over
+                let column = column.clone();
+                glib::idle_add_local(move || {
+                    dropped(index, &column);
+                    glib::Continue(false)
+                });
+            }
+        });
+        widget
+    }
+
+    /// A task's card with its name, sub category and due date
+    fn card(&self, task : &BoardTask) -> gtk::EventBox {
+        let inner = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        let name = gtk::Label::new(Some(&task.name));
+        name.set_halign(gtk::Align::Start);
+        name.set_line_wrap(true);
+        name.set_xalign(0.0);
+        inner.pack_start(&name, false, false, 0);
+
+        let mut details = Vec::new();
+        if !task.sub_category.is_empty() {
+            details.push(task.sub_category.clone());
+        }
+        if !task.due.is_empty() {
+            details.push(format!("due {}", task.due));
+        }
+        if !details.is_empty() {
+            let label = gtk::Label::new(Some(&details.join(" · ")));
+            label.set_halign(gtk::Align::Start);
+            label.get_style_context().add_class("dim-label");
+            inner.pack_start(&label, false, false, 0);
+        }
+
+        let card = gtk::EventBox::new();
+        card.add(&inner);
+        let style = card.get_style_context();
+        style.add_class("board-card");
+        style.add_class(priority_class(task.priority));
+        if task.done {
+            style.add_class("done");
+        }
+
+        let targets = [gtk::TargetEntry::new(crate::TASK_TARGET, gtk::TargetFlags::SAME_APP, 0)];
+        card.drag_source_set(gdk::ModifierType::BUTTON1_MASK, &targets, gdk::DragAction::MOVE);
+        let index = task.index;
+        card.connect_drag_data_get(move |_, _, data, _, _| {
+            data.set(&data.get_target(), 8, index.to_string().as_bytes());
+        });
+        let activated = Rc::clone(&self.activated);
+        card.connect_button_press_event(move |_, _| {
+            let activated = activated.borrow().clone();
+            if let Some(activated) = activated {
+                activated(index);
+            }
+            gtk::Inhibit(false)
+        });
+        card
+    }
+}
@@ -0,0 +1,295 @@
+//! Month view of the tasks by their due date, the `calendar` page of the `views` stack
+
+use crate::dates;
+use crate::priority::priority_class;
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+const MONTHS : [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+const WEEKDAYS : [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+/// Six weeks always fit a month, however it falls
+const SHOWN_DAYS : i64 = 42;
+/// Tasks a day lists before it just says how many more there are
+const TASKS_PER_DAY : usize = 3;
+
+/// A task as the calendar sees it
+pub struct CalendarTask {
+    /// Index in `TasksManager::tasks`
+    pub index : usize,
+    pub name : String,
+    /// Days since the epoch
+    pub due : i64,
+    pub priority : u32,
+    pub done : bool,
+}
+
+#[derive(Clone)]
+struct DayCell {
+    frame : gtk::EventBox,
+    number : gtk::Label,
+    tasks : gtk::Box,
+}
+
+#[derive(Clone)]
+pub struct CalendarView {
+    title : gtk::Label,
+    cells : Vec<DayCell>,
+    day_title : gtk::Label,
+    day_list : gtk::ListBox,
+    /// Year and month(1-12) being shown
+    month : Rc<Cell<(i64, u32)>>,
+    /// The day `day_list` lists, in days since the epoch
+    selected : Rc<Cell<i64>>,
+    /// What the last `show` got, kept around for moving between months
+    tasks : Rc<RefCell<Vec<CalendarTask>>>,
+    /// Task index of every row in `day_list`
+    listed : Rc<RefCell<Vec<usize>>>,
+}
+impl CalendarView {
+    /// Fills `root` with the calendar, starting at today's month
+    pub fn new(root : &gtk::Box) -> CalendarView {
+        let header = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        let prev = gtk::Button::from_icon_name(Some("go-previous-symbolic"), gtk::IconSize::Button);
+        let next = gtk::Button::from_icon_name(Some("go-next-symbolic"), gtk::IconSize::Button);
+        let today = gtk::Button::with_label("Today");
+        let title = gtk::Label::new(None);
+        header.pack_start(&prev, false, false, 0);
+        header.pack_start(&title, true, true, 0);
+        header.pack_start(&today, false, false, 0);
+        header.pack_start(&next, false, false, 0);
+        root.pack_start(&header, false, false, 0);
+
+        let names = gtk::Grid::new();
+        names.set_column_homogeneous(true);
+        for (col, name) in WEEKDAYS.iter().enumerate() {
+            names.attach(&gtk::Label::new(Some(name)), col as i32, 0, 1, 1);
+        }
+        root.pack_start(&names, false, false, 0);
+
+        let grid = gtk::Grid::new();
+        grid.set_column_homogeneous(true);
+        grid.set_row_homogeneous(true);
+        let targets = [gtk::TargetEntry::new(crate::TASK_TARGET, gtk::TargetFlags::SAME_APP, 0)];
+        let mut cells = Vec::new();
+        for i in 0..SHOWN_DAYS as i32 {
+            let frame = gtk::EventBox::new();
+            frame.get_style_context().add_class("calendar-day");
+            frame.drag_dest_set(gtk::DestDefaults::ALL, &targets, gdk::DragAction::MOVE);
+            let inner = gtk::Box::new(gtk::Orientation::Vertical, 1);
+            let number = gtk::Label::new(None);
+            number.set_halign(gtk::Align::End);
+            number.get_style_context().add_class("day-number");
+            let tasks = gtk::Box::new(gtk::Orientation::Vertical, 1);
+            inner.pack_start(&number, false, false, 0);
+            inner.pack_start(&tasks, true, true, 0);
+            frame.add(&inner);
+            grid.attach(&frame, i % 7, i / 7, 1, 1);
+            cells.push(DayCell { frame, number, tasks });
+        }
+        root.pack_start(&grid, true, true, 0);
+
+        let day_title = gtk::Label::new(None);
+        day_title.set_halign(gtk::Align::Start);
+        root.pack_start(&day_title, false, false, 0);
+        let day_list = gtk::ListBox::new();
+        let scroll = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+        scroll.set_size_request(-1, 120);
+        scroll.set_shadow_type(gtk::ShadowType::In);
+        scroll.add(&day_list);
+        root.pack_start(&scroll, false, true, 0);
+
+        let (year, month, _) = dates::civil_from_days(dates::today());
+        let view = CalendarView {
+            title,
+            cells,
+            day_title,
+            day_list,
+            month : Rc::new(Cell::new((year, month))),
+            selected : Rc::new(Cell::new(dates::today())),
+            tasks : Rc::new(RefCell::new(Vec::new())),
+            listed : Rc::new(RefCell::new(Vec::new())),
+        };
+
+        // Connect moving between months
+        let clone = view.clone();
+        prev.connect_clicked(move |_| {
+            let (year, month) = clone.month.get();
+            clone.month.set(if month == 1 { (year - 1, 12) } else { (year, month - 1) });
+            clone.redraw();
+        });
+        let clone = view.clone();
+        next.connect_clicked(move |_| {
+            let (year, month) = clone.month.get();
+            clone.month.set(if month == 12 { (year + 1, 1) } else { (year, month + 1) });
+            clone.redraw();
+        });
+        let clone = view.clone();
+        today.connect_clicked(move |_| {
+            let today = dates::today();
+            let (year, month, _) = dates::civil_from_days(today);
+            clone.month.set((year, month));
+            clone.selected.set(today);
+            clone.redraw();
+        });
+
+        // Connect clicking a day, which lists its tasks below
+        for (i, cell) in view.cells.iter().enumerate() {
+            let clone = view.clone();
+            cell.frame.connect_button_press_event(move |_, _| {
+                clone.selected.set(clone.first_shown() + i as i64);
+                clone.redraw();
+                gtk::Inhibit(false)
+            });
+        }
+
+        view.redraw();
+        view
+    }
+
+    /// Shows `tasks` from now on, ones without a due date are simply never shown
+    pub fn show(&self, mut tasks : Vec<CalendarTask>) {
+        // Most important first, those are the ones a full day still lists
+        tasks.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.index.cmp(&b.index)));
+        *self.tasks.borrow_mut() = tasks;
+        self.redraw();
+    }
+
+    /// Calls `activated` with the task's index when a task in the day's list is activated
+    pub fn connect_task_activated<F : Fn(usize) + 'static>(&self, activated : F) {
+        let listed = Rc::clone(&self.listed);
+        self.day_list.connect_row_activated(move |_, row| {
+            let index = listed.borrow().get(row.get_index() as usize).copied();
+            if let Some(index) = index {
+                activated(index);
+            }
+        });
+    }
+
+    /// Calls `dropped` with the task's index and the day(since the epoch) when a task is dragged onto a day
+    pub fn connect_task_dropped<F : Fn(usize, i64) + 'static>(&self, dropped : F) {
+        let dropped = Rc::new(dropped);
+        for (i, cell) in self.cells.iter().enumerate() {
+            let clone = self.clone();
+            let dropped = Rc::clone(&dropped);
+            cell.frame.connect_drag_data_received(move |_, _, _, _, data, _, _| {
+                let index = String::from_utf8(data.get_data()).ok().and_then(|s| s.parse::<usize>().ok());
+                if let Some(index) = index {
+                    let day = clone.first_shown() + i as i64;
+                    // Changing the task redraws the calendar, the dragged widget included,
+                    // which is better left until the drag is over
+                    let dropped = Rc::clone(&dropped);
+                    glib::idle_add_local(move || {
+                        dropped(index, day);
+                        glib::Continue(false)
+                    });
+                }
+            });
+        }
+    }
+
+    /// First day in the grid, the monday on or before the 1st
+    fn first_shown(&self) -> i64 {
+        let (year, month) = self.month.get();
+        let first = dates::days_from_civil(year, month, 1);
+        first - dates::weekday(first)
+    }
+
+    fn redraw(&self) {
+        let (year, month) = self.month.get();
+        self.title.set_markup(&format!("<b>{} {}</b>", MONTHS[month as usize - 1], year));
+
+        let first = self.first_shown();
+        let today = dates::today();
+        let tasks = self.tasks.borrow();
+        for (i, cell) in self.cells.iter().enumerate() {
+            let day = first + i as i64;
+            let (_, day_month, day_of_month) = dates::civil_from_days(day);
+            cell.number.set_text(&day_of_month.to_string());
+
+            for child in cell.tasks.get_children() {
+                cell.tasks.remove(&child);
+            }
+            let due : Vec<&CalendarTask> = tasks.iter().filter(|t| t.due == day).collect();
+            for task in due.iter().take(TASKS_PER_DAY) {
+                cell.tasks.pack_start(&task_widget(task), false, false, 0);
+            }
+            if due.len() > TASKS_PER_DAY {
+                let more = gtk::Label::new(Some(&format!("+{} more", due.len() - TASKS_PER_DAY)));
+                more.set_halign(gtk::Align::Start);
+                cell.tasks.pack_start(&more, false, false, 0);
+            }
+            cell.tasks.show_all();
+
+            set_class(&cell.frame, "other-month", day_month != month);
+            set_class(&cell.frame, "today", day == today);
+            set_class(&cell.frame, "overdue", day < today && due.iter().any(|t| !t.done));
+            set_class(&cell.frame, "selected", day == self.selected.get());
+        }
+        drop(tasks);
+        self.redraw_day();
+    }
+
+    /// Fills `day_list` with every task due on the selected day
+    fn redraw_day(&self) {
+        let day = self.selected.get();
+        for child in self.day_list.get_children() {
+            self.day_list.remove(&child);
+        }
+
+        let tasks = self.tasks.borrow();
+        let due : Vec<&CalendarTask> = tasks.iter().filter(|t| t.due == day).collect();
+        let (year, month, day_of_month) = dates::civil_from_days(day);
+        let date = format!("{} {} {}", day_of_month, MONTHS[month as usize - 1], year);
+        if due.is_empty() {
+            self.day_title.set_text(&format!("Nothing due on {}", date));
+        }
+        else {
+            self.day_title.set_text(&format!("Due on {}", date));
+        }
+
+        for task in due.iter() {
+            self.day_list.add(&task_widget(task));
+        }
+        *self.listed.borrow_mut() = due.iter().map(|t| t.index).collect();
+        self.day_list.show_all();
+    }
+}
+
+/// A task's name, which can be dragged onto another day
+fn task_widget(task : &CalendarTask) -> gtk::EventBox {
+    let label = gtk::Label::new(Some(&task.name));
+    label.set_halign(gtk::Align::Start);
+    label.set_ellipsize(pango::EllipsizeMode::End);
+    label.set_tooltip_text(Some(&format!("{} (priority {})", task.name, task.priority)));
+
+    let widget = gtk::EventBox::new();
+    widget.add(&label);
+    let style = widget.get_style_context();
+    style.add_class("calendar-task");
+    style.add_class(priority_class(task.priority));
+    if task.done {
+        style.add_class("done");
+    }
+
+    let targets = [gtk::TargetEntry::new(crate::TASK_TARGET, gtk::TargetFlags::SAME_APP, 0)];
+    widget.drag_source_set(gdk::ModifierType::BUTTON1_MASK, &targets, gdk::DragAction::MOVE);
+    let index = task.index;
+    widget.connect_drag_data_get(move |_, _, data, _, _| {
+        data.set(&data.get_target(), 8, index.to_string().as_bytes());
+    });
+    widget
+}
+
+fn set_class<W : IsA<gtk::Widget>>(widget : &W, class : &str, on : bool) {
+    let style = widget.get_style_context();
+    if on {
+        style.add_class(class);
+    }
+    else {
+        style.remove_class(class);
+    }
+}
@@ -0,0 +1,485 @@
+//! Tasks as CSV, and the dialog mapping the columns of a file being imported onto task fields
+
+use crate::dates;
+use crate::merge;
+use crate::priority::MAX_PRIORITY;
+use crate::undo::TaskData;
+use cool_organizer::*;
+use gtk::prelude::*;
+use std::rc::Rc;
+
+/// Rows the import dialog previews
+const PREVIEW_ROWS : usize = 5;
+
+/// A field of a task a column can hold
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Name,
+    Category,
+    SubCategory,
+    Due,
+    Priority,
+    Done,
+}
+impl Field {
+    /// In the order exported files have them
+    pub const ALL : [Field; 6] = [Field::Name, Field::Category, Field::SubCategory, Field::Due, Field::Priority, Field::Done];
+
+    /// Also what the header of an exported file says
+    fn header(self) -> &'static str {
+        match self {
+            Field::Name => "name",
+            Field::Category => "category",
+            Field::SubCategory => "sub_category",
+            Field::Due => "due",
+            Field::Priority => "priority",
+            Field::Done => "done",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Field::Name => "Name",
+            Field::Category => "Category",
+            Field::SubCategory => "Sub Category",
+            Field::Due => "Due",
+            Field::Priority => "Priority",
+            Field::Done => "Done",
+        }
+    }
+}
+
+/// How the due column writes its dates
+#[derive(Clone, Copy, PartialEq)]
+pub enum DateFormat {
+    /// `YYYY-MM-DD`
+    Iso,
+    /// `DD/MM/YYYY`, dots and dashes work too
+    DayMonthYear,
+    /// `MM/DD/YYYY`
+    MonthDayYear,
+}
+impl DateFormat {
+    /// In the order `detect` prefers them
+    pub const ALL : [DateFormat; 3] = [DateFormat::Iso, DateFormat::DayMonthYear, DateFormat::MonthDayYear];
+
+    fn title(self) -> &'static str {
+        match self {
+            DateFormat::Iso => "YYYY-MM-DD",
+            DateFormat::DayMonthYear => "DD/MM/YYYY",
+            DateFormat::MonthDayYear => "MM/DD/YYYY",
+        }
+    }
+
+    /// Days since the epoch
+    pub fn parse(self, date : &str) -> Option<i64> {
+        let date = date.trim();
+        if self == DateFormat::Iso {
+            return dates::parse_iso(date);
+        }
+        let mut parts = date.splitn(3, ['/', '.', '-']);
+        let first : u32 = parts.next()?.trim().parse().ok()?;
+        let second : u32 = parts.next()?.trim().parse().ok()?;
+        let year : i64 = parts.next()?.trim().parse().ok()?;
+        match self {
+            DateFormat::DayMonthYear => dates::checked_days(year, second, first),
+            _ => dates::checked_days(year, first, second),
+        }
+    }
+
+    /// The first format every non-empty date in `dates` parses with, ISO if none does
+    pub fn detect(dates : &[&str]) -> DateFormat {
+        let dates : Vec<&&str> = dates.iter().filter(|d| !d.trim().is_empty()).collect();
+        DateFormat::ALL.iter()
+            .copied()
+            .find(|format| dates.iter().all(|d| format.parse(d).is_some()))
+            .unwrap_or(DateFormat::Iso)
+    }
+}
+
+/// Rows of `text`, quoted fields can hold commas, quotes(doubled) and line breaks. Blank lines are skipped
+pub fn parse(text : &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => (),
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+    row.push(field);
+    if row.iter().any(|f| !f.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+/// `tasks` with a header row, dates as `YYYY-MM-DD`
+pub fn write(tasks : &[&Task]) -> String {
+    let header : Vec<&str> = Field::ALL.iter().map(|f| f.header()).collect();
+    let mut csv = header.join(",");
+    csv.push_str("\r\n");
+    for task in tasks.iter() {
+        let fields = [
+            quote(&task.name),
+            quote(&task.category),
+            quote(&task.sub_category),
+            dates::format_due(&task.due),
+            task.priority.to_string(),
+            task.done.to_string(),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Quotes a field if it has anything that would break the row apart
+fn quote(field : &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        field.to_string()
+    }
+}
+
+/// Which column every field comes from
+pub struct Mapping {
+    /// Indexed like `Field::ALL`
+    pub columns : [Option<usize>; 6],
+    /// The first row names the columns instead of being a task
+    pub header : bool,
+    pub dates : DateFormat,
+}
+impl Mapping {
+    /// Matches the columns to fields by the header, or goes by the order exports have them if there is none
+    pub fn guess(rows : &[Vec<String>]) -> Mapping {
+        let first = rows.first().map(|row| row.as_slice()).unwrap_or(&[]);
+        let mut columns = [None; 6];
+        for (i, field) in Field::ALL.iter().enumerate() {
+            columns[i] = first.iter().position(|name| {
+                let name = name.trim().to_lowercase().replace(' ', "_");
+                name == field.header() || name == field.title().to_lowercase()
+            });
+        }
+
+        let header = columns.iter().any(|c| c.is_some());
+        if !header {
+            let width = first.len();
+            for (i, column) in columns.iter_mut().enumerate() {
+                *column = if i < width { Some(i) } else { None };
+            }
+        }
+        let mut mapping = Mapping { columns, header, dates : DateFormat::Iso };
+        mapping.dates = mapping.detect_dates(rows);
+        mapping
+    }
+
+    /// Date format of the due column
+    pub fn detect_dates(&self, rows : &[Vec<String>]) -> DateFormat {
+        let due : Vec<&str> = self.records(rows).iter()
+            .filter_map(|row| self.get(row, Field::Due))
+            .collect();
+        DateFormat::detect(&due)
+    }
+
+    /// The rows which are tasks
+    pub fn records<'a>(&self, rows : &'a [Vec<String>]) -> &'a [Vec<String>] {
+        if self.header && !rows.is_empty() { &rows[1..] } else { rows }
+    }
+
+    fn get<'a>(&self, row : &'a [String], field : Field) -> Option<&'a str> {
+        let i = Field::ALL.iter().position(|f| *f == field)?;
+        let column = self.columns[i]?;
+        row.get(column).map(|value| value.trim())
+    }
+
+    /// The task a row describes, `None` if it has no name. Values which don't make sense are left out
+    pub fn task(&self, row : &[String]) -> Option<TaskData> {
+        let name = self.get(row, Field::Name).filter(|name| !name.is_empty())?;
+        let mut task = TaskData::from_task(&Task::new(name));
+        if let Some(category) = self.get(row, Field::Category).filter(|c| !c.is_empty()) {
+            task.category = category.to_string();
+        }
+        if let Some(sub_category) = self.get(row, Field::SubCategory) {
+            task.sub_category = sub_category.to_string();
+        }
+        if let Some(due) = self.get(row, Field::Due).and_then(|due| self.dates.parse(due)).and_then(dates::date_from_days) {
+            task.due = Some(due);
+        }
+        if let Some(priority) = self.get(row, Field::Priority).and_then(|p| p.parse::<u8>().ok()) {
+            task.priority = priority.min(MAX_PRIORITY);
+        }
+        if let Some(done) = self.get(row, Field::Done) {
+            task.done = matches!(done.to_lowercase().as_str(), "true" | "yes" | "y" | "x" | "1" | "done");
+        }
+        Some(task)
+    }
+}
+
+/// Whether `task` is already in `existing`, the way every import matches tasks(see `merge::same_task`)
+pub fn is_duplicate(task : &TaskData, existing : &[TaskData]) -> bool {
+    existing.iter().any(|e| merge::same_task(e, task))
+}
+
+/// Asks how the columns of `rows` map onto task fields, previewing the first few tasks.
+/// `None` if cancelled, otherwise the tasks to import
+pub fn ask_import(parent : &gtk::Window, rows : Vec<Vec<String>>, existing : Vec<TaskData>) -> Option<Vec<TaskData>> {
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Import CSV"),
+        Some(parent),
+        gtk::DialogFlags::MODAL,
+        &[("Cancel", gtk::ResponseType::Cancel), ("Import", gtk::ResponseType::Accept)],
+    );
+    dialog.set_default_response(gtk::ResponseType::Accept);
+    let content = dialog.get_content_area();
+    content.set_spacing(6);
+
+    let mapping = Mapping::guess(&rows);
+    let header = gtk::CheckButton::with_label("The first row names the columns");
+    header.set_active(mapping.header);
+    content.add(&header);
+
+    // A combo box per field, picking its column
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(4);
+    grid.set_column_spacing(8);
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut combos = Vec::new();
+    for (i, field) in Field::ALL.iter().enumerate() {
+        let label = gtk::Label::new(Some(field.title()));
+        label.set_halign(gtk::Align::End);
+        let combo = gtk::ComboBoxText::new();
+        combo.append(Some("none"), "(not in the file)");
+        for column in 0..width {
+            let name = match rows.first().and_then(|row| row.get(column)) {
+                Some(name) if !name.is_empty() => format!("Column {}: {}", column + 1, name),
+                _ => format!("Column {}", column + 1),
+            };
+            combo.append(Some(&column.to_string()), &name);
+        }
+        let active = mapping.columns[i].map(|c| c.to_string()).unwrap_or_else(|| "none".to_string());
+        combo.set_active_id(Some(&active));
+        grid.attach(&label, 0, i as i32, 1, 1);
+        grid.attach(&combo, 1, i as i32, 1, 1);
+        combos.push(combo);
+    }
+    let date_label = gtk::Label::new(Some("Dates look like"));
+    date_label.set_halign(gtk::Align::End);
+    let date_combo = gtk::ComboBoxText::new();
+    for (i, format) in DateFormat::ALL.iter().enumerate() {
+        date_combo.append(Some(&i.to_string()), format.title());
+    }
+    let date_id = |format : DateFormat| DateFormat::ALL.iter().position(|f| *f == format).unwrap_or(0).to_string();
+    date_combo.set_active_id(Some(&date_id(mapping.dates)));
+    grid.attach(&date_label, 0, Field::ALL.len() as i32, 1, 1);
+    grid.attach(&date_combo, 1, Field::ALL.len() as i32, 1, 1);
+    content.add(&grid);
+
+    // The first few tasks as they would come out
+    let preview_store = gtk::ListStore::new(&[glib::Type::String; 6]);
+    let preview = gtk::TreeView::with_model(&preview_store);
+    for (i, field) in Field::ALL.iter().enumerate() {
+        let renderer = gtk::CellRendererText::new();
+        let col = gtk::TreeViewColumn::new();
+        col.set_title(field.title());
+        col.pack_start(&renderer, true);
+        col.add_attribute(&renderer, "text", i as i32);
+        preview.append_column(&col);
+    }
+    content.add(&preview);
+    let summary = gtk::Label::new(None);
+    summary.set_halign(gtk::Align::Start);
+    content.add(&summary);
+    let skip = gtk::CheckButton::with_label("Skip tasks which are already in the list");
+    skip.set_active(true);
+    content.add(&skip);
+
+    let rows = Rc::new(rows);
+    let read = {
+        let (header, combos, date_combo) = (header.clone(), combos.clone(), date_combo.clone());
+        Rc::new(move || {
+            let mut columns = [None; 6];
+            for (column, combo) in columns.iter_mut().zip(combos.iter()) {
+                *column = combo.get_active_id().and_then(|id| id.parse::<usize>().ok());
+            }
+            let format = date_combo.get_active_id()
+                .and_then(|id| id.parse::<usize>().ok())
+                .and_then(|i| DateFormat::ALL.get(i).copied())
+                .unwrap_or(DateFormat::Iso);
+            Mapping { columns, header : header.get_active(), dates : format }
+        })
+    };
+    let existing = Rc::new(existing);
+    let refresh = {
+        let (rows, read, existing) = (Rc::clone(&rows), Rc::clone(&read), Rc::clone(&existing));
+        let (preview_store, summary) = (preview_store.clone(), summary.clone());
+        Rc::new(move || {
+            let mapping = read();
+            let tasks : Vec<TaskData> = mapping.records(&rows).iter().filter_map(|row| mapping.task(row)).collect();
+            preview_store.clear();
+            for task in tasks.iter().take(PREVIEW_ROWS) {
+                let values = [
+                    task.name.clone(),
+                    task.category.clone(),
+                    task.sub_category.clone(),
+                    dates::format_due(&task.due),
+                    task.priority.to_string(),
+                    task.done.to_string(),
+                ];
+                let values : Vec<&dyn ToValue> = values.iter().map(|v| v as &dyn ToValue).collect();
+                preview_store.insert_with_values(None, &[0, 1, 2, 3, 4, 5], &values);
+            }
+            let duplicates = tasks.iter().filter(|t| is_duplicate(t, &existing)).count();
+            let skipped = mapping.records(&rows).len() - tasks.len();
+            summary.set_text(&format!(
+                "{} tasks, {} of them already in the list. {} rows without a name are left out",
+                tasks.len(), duplicates, skipped,
+            ));
+        })
+    };
+
+    let r = Rc::clone(&refresh);
+    header.connect_toggled(move |_| r());
+    for (i, combo) in combos.iter().enumerate() {
+        let r = Rc::clone(&refresh);
+        let (rows, read, date_combo) = (Rc::clone(&rows), Rc::clone(&read), date_combo.clone());
+        combo.connect_changed(move |_| {
+            // A new due column might write its dates differently
+            if Field::ALL[i] == Field::Due {
+                date_combo.set_active_id(Some(&date_id(read().detect_dates(&rows))));
+            }
+            r();
+        });
+    }
+    let r = Rc::clone(&refresh);
+    date_combo.connect_changed(move |_| r());
+    refresh();
+
+    dialog.show_all();
+    let response = dialog.run();
+    let mapping = read();
+    let skip_duplicates = skip.get_active();
+    dialog.close();
+    if response != gtk::ResponseType::Accept {
+        return None;
+    }
+
+    let tasks = mapping.records(&rows).iter()
+        .filter_map(|row| mapping.task(row))
+        .filter(|task| !(skip_duplicates && is_duplicate(task, &existing)))
+        .collect();
+    Some(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(rows : &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter().map(|row| row.iter().map(|f| f.to_string()).collect()).collect()
+    }
+
+    #[test]
+    fn quoted_fields() {
+        let text = "name,category\r\n\"Buy milk, eggs\",home\r\n\"Say \"\"hi\"\"\",\"two\nlines\"\r\n\r\nlast,";
+        assert_eq!(parse(text), rows(&[
+            &["name", "category"],
+            &["Buy milk, eggs", "home"],
+            &["Say \"hi\"", "two\nlines"],
+            &["last", ""],
+        ]));
+        assert!(parse("\n,,\r\n").is_empty());
+    }
+
+    #[test]
+    fn write_parses_back() {
+        let mut task = Task::new("a, \"b\"\nc");
+        task.category = "x".to_string();
+        task.sub_category = String::new();
+        task.due = None;
+        let parsed = parse(&write(&[&task]));
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1][0], task.name);
+        assert_eq!(parsed[1][1], "x");
+    }
+
+    #[test]
+    fn guess_by_header() {
+        let rows = rows(&[&["Done", "Sub Category", "NAME", "due"], &["yes", "backend", "Fix bug", "31/01/2024"]]);
+        let mapping = Mapping::guess(&rows);
+        assert!(mapping.header);
+        assert_eq!(mapping.columns, [Some(2), None, Some(1), Some(3), None, Some(0)]);
+        assert!(mapping.dates == DateFormat::DayMonthYear);
+        assert_eq!(mapping.records(&rows).len(), 1);
+
+        let task = mapping.task(&rows[1]).unwrap();
+        assert_eq!(task.name, "Fix bug");
+        assert_eq!(task.sub_category, "backend");
+        assert!(task.done);
+        assert_eq!(dates::due_days(&task.due), dates::days_from_civil(2024, 1, 31));
+    }
+
+    #[test]
+    fn guess_without_header() {
+        let rows = rows(&[&["Fix bug", "work", "backend"], &["Buy milk", "home", ""]]);
+        let mapping = Mapping::guess(&rows);
+        assert!(!mapping.header);
+        assert_eq!(mapping.columns, [Some(0), Some(1), Some(2), None, None, None]);
+        assert_eq!(mapping.records(&rows).len(), 2);
+    }
+
+    #[test]
+    fn detect_dates() {
+        assert!(DateFormat::detect(&["2024-01-31", "", "2024-12-01"]) == DateFormat::Iso);
+        assert!(DateFormat::detect(&["31/01/2024", "01.12.2024"]) == DateFormat::DayMonthYear);
+        assert!(DateFormat::detect(&["01/31/2024", "12/01/2024"]) == DateFormat::MonthDayYear);
+        // Fits both, the day comes first
+        assert!(DateFormat::detect(&["01/02/2024"]) == DateFormat::DayMonthYear);
+        assert!(DateFormat::detect(&["soon"]) == DateFormat::Iso);
+
+        assert_eq!(DateFormat::MonthDayYear.parse("02/30/2024"), None);
+        assert_eq!(DateFormat::DayMonthYear.parse("1/1/9223372036854775807"), None);
+        assert_eq!(DateFormat::DayMonthYear.parse("1/1/-9223372036854775808"), None);
+    }
+
+    #[test]
+    fn duplicates() {
+        let task = |name : &str, category : &str| {
+            let mut task = TaskData::from_task(&Task::new(name));
+            task.category = category.to_string();
+            task.sub_category = String::new();
+            task
+        };
+        let existing = [task("Buy milk", "home"), task("Fix bug", "work")];
+        assert!(is_duplicate(&task(" Buy  milk ", "home"), &existing));
+        assert!(!is_duplicate(&task("buy MILK", "home"), &existing));
+        assert!(!is_duplicate(&task("Buy milk", "work"), &existing));
+        assert!(!is_duplicate(&task("Buy bread", "home"), &existing));
+    }
+}
@@ -0,0 +1,151 @@
+//! Turning `Date`s into plain numbers the ui can sort and compare
+
+use cool_organizer::*;
+
+/// Sort value of a task without a due date, after every real date
+pub const NO_DUE : i64 = i64::MAX;
+
+/// Dates go from the year 1 to 9999, which keeps the arithmetic far from overflowing
+const YEARS : std::ops::RangeInclusive<i64> = 1..=9999;
+/// 0001-01-01 and 9999-12-31 in days since the epoch
+const FIRST_DAY : i64 = -719162;
+const LAST_DAY : i64 = 2932896;
+
+/// Year, month(1-12) and day(1-31) of the date
+pub fn ymd(date : &Date) -> (i64, u32, u32) {
+    let date = date.to_localdate().expect("error converting date");
+    (date.year(), date.month() as u32, date.day() as u32)
+}
+
+/// Days since 1970-01-01 for a date in the proleptic gregorian calendar
+pub fn days_from_civil(year : i64, month : u32, day : u32) -> i64 {
+    // Shift the year to start at march so the leap day is the last day of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+/// Year, month(1-12) and day(1-31) of the date `days` days after 1970-01-01, the inverse of `days_from_civil`
+pub fn civil_from_days(days : i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = (if days >= 0 { days } else { days - 146096 }) / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from march again
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month as u32, day as u32)
+}
+
+/// Days since the epoch, `None` for dates which don't exist(the 31st of february and friends)
+/// or are outside of the years 1 to 9999
+pub fn checked_days(year : i64, month : u32, day : u32) -> Option<i64> {
+    if !YEARS.contains(&year) {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    if civil_from_days(days) == (year, month, day) {
+        Some(days)
+    }
+    else {
+        None
+    }
+}
+
+/// Days since the epoch of a `YYYY-MM-DD` date
+pub fn parse_iso(date : &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-');
+    let year : i64 = parts.next()?.parse().ok()?;
+    let month : u32 = parts.next()?.parse().ok()?;
+    let day : u32 = parts.next()?.parse().ok()?;
+    checked_days(year, month, day)
+}
+
+/// Whether `days` is a date a task can have, see `checked_days`
+pub fn in_range(days : i64) -> bool {
+    (FIRST_DAY..=LAST_DAY).contains(&days)
+}
+
+/// The `Date` `days` days after the epoch, `None` if it's out of range
+pub fn date_from_days(days : i64) -> Option<Date> {
+    if !in_range(days) {
+        return None;
+    }
+    let (y, m, d) = civil_from_days(days);
+    LocalDate::ymd(y, Date::month_from_int(m as i32), d as i8).ok().map(Date::from)
+}
+
+/// Day of the week, 0 for monday up to 6 for sunday
+pub fn weekday(day : i64) -> i64 {
+    // 1970-01-01 was a thursday
+    (day + 3).rem_euclid(7)
+}
+
+/// Sortable value of a due date, `NO_DUE` if there is none
+pub fn due_days(due : &Option<Date>) -> i64 {
+    match due {
+        Some(date) => {
+            let (y, m, d) = ymd(date);
+            days_from_civil(y, m, d)
+        }
+        None => NO_DUE,
+    }
+}
+
+/// `YYYY-MM-DD`, or an empty string when there is no due date
+pub fn format_due(due : &Option<Date>) -> String {
+    match due {
+        Some(date) => {
+            let (y, m, d) = ymd(date);
+            format!("{}-{:02}-{:02}", y, m, d)
+        }
+        None => String::new(),
+    }
+}
+
+/// Today's local date in days since the epoch
+pub fn today() -> i64 {
+    let (y, m, d) = glib::DateTime::new_now_local().get_ymd();
+    days_from_civil(y as i64, m as u32, d as u32)
+}
+
+/// First(monday) and last(sunday) day of the week `day` is in
+pub fn week_of(day : i64) -> (i64, i64) {
+    let start = day - weekday(day);
+    (start, start + 6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_days_round_trip() {
+        for days in [-719468, -1, 0, 59, 11016, 19725, 2932896].iter() {
+            let (y, m, d) = civil_from_days(*days);
+            assert_eq!(days_from_civil(y, m, d), *days);
+        }
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+    }
+
+    #[test]
+    fn range() {
+        assert_eq!(checked_days(1, 1, 1), Some(FIRST_DAY));
+        assert_eq!(checked_days(9999, 12, 31), Some(LAST_DAY));
+        assert_eq!(checked_days(0, 12, 31), None);
+        assert_eq!(checked_days(10000, 1, 1), None);
+        assert_eq!(checked_days(2023, 2, 29), None);
+        assert_eq!(parse_iso("9223372036854775807-01-01"), None);
+        assert_eq!(parse_iso("-9223372036854775807-01-01"), None);
+        assert!(in_range(LAST_DAY) && !in_range(LAST_DAY + 1) && !in_range(i64::MAX));
+    }
+}
@@ -0,0 +1,56 @@
+//! What the search bar above the tasks list lets through
+
+/// The parts of a task the filter cares about
+pub struct FilterRow<'a> {
+    pub name : &'a str,
+    pub category : &'a str,
+    pub sub_category : &'a str,
+    /// Days since the epoch, `dates::NO_DUE` when there is no due date
+    pub due : i64,
+    pub priority : u32,
+    pub done : bool,
+}
+
+#[derive(Default)]
+pub struct TaskFilter {
+    /// Already lowercased, matched as a substring of the name, category or sub category
+    pub text : String,
+    pub hide_done : bool,
+    pub overdue_only : bool,
+    pub due_this_week : bool,
+    /// Only let through tasks with at least this priority
+    pub min_priority : Option<u32>,
+}
+impl TaskFilter {
+    /// Whether the filter lets everything through
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+            && !self.hide_done
+            && !self.overdue_only
+            && !self.due_this_week
+            && self.min_priority.is_none()
+    }
+
+    /// `today` is in days since the epoch, same as `FilterRow::due`
+    pub fn matches(&self, row : &FilterRow, today : i64) -> bool {
+        if !self.text.is_empty()
+            && !row.name.to_lowercase().contains(&self.text)
+            && !row.category.to_lowercase().contains(&self.text)
+            && !row.sub_category.to_lowercase().contains(&self.text) {
+            return false;
+        }
+        if self.hide_done && row.done {
+            return false;
+        }
+        if self.overdue_only && (row.done || row.due >= today) {
+            return false;
+        }
+        if self.due_this_week {
+            let (start, end) = crate::dates::week_of(today);
+            if row.due < start || row.due > end {
+                return false;
+            }
+        }
+        !matches!(self.min_priority, Some(min) if row.priority < min)
+    }
+}
@@ -0,0 +1,72 @@
+//! Editing one or a bunch of tasks at once through the task grid
+
+use cool_organizer::*;
+use crate::undo::TaskData;
+
+/// Fields of the task grid edited since it was filled in, only those get written to the tasks
+#[derive(Clone, Copy, Default)]
+pub struct Touched {
+    pub name : bool,
+    pub category : bool,
+    pub sub_category : bool,
+    pub due : bool,
+    pub done : bool,
+    pub priority : bool,
+}
+impl Touched {
+    pub fn any(&self) -> bool {
+        self.name || self.category || self.sub_category || self.due || self.done || self.priority
+    }
+
+    /// Copies the touched fields of `from`(what the grid says) over `to`
+    pub fn copy(&self, from : &TaskData, to : &mut TaskData) {
+        if self.name {
+            to.name = from.name.clone();
+        }
+        if self.category {
+            to.category = from.category.clone();
+        }
+        if self.sub_category {
+            to.sub_category = from.sub_category.clone();
+        }
+        if self.due {
+            to.due = from.due;
+        }
+        if self.done {
+            to.done = from.done;
+        }
+        if self.priority {
+            to.priority = from.priority;
+        }
+    }
+}
+
+/// Marks the field a task grid widget edits as touched
+pub type Touch = fn(&mut Touched);
+
+/// What the tasks in the grid agree on, `None` for "mixed" fields
+pub struct Common {
+    pub name : Option<String>,
+    pub category : Option<String>,
+    pub sub_category : Option<String>,
+    pub due : Option<Option<Date>>,
+    pub done : Option<bool>,
+    pub priority : Option<u8>,
+}
+impl Common {
+    /// `tasks` must not be empty
+    pub fn of(tasks : &[&Task]) -> Common {
+        let first = tasks[0];
+        let all = |same : &dyn Fn(&Task) -> bool| tasks.iter().all(|t| same(t));
+        let due = crate::dates::due_days(&first.due);
+
+        Common {
+            name : if all(&|t| t.name == first.name) { Some(first.name.clone()) } else { None },
+            category : if all(&|t| t.category == first.category) { Some(first.category.clone()) } else { None },
+            sub_category : if all(&|t| t.sub_category == first.sub_category) { Some(first.sub_category.clone()) } else { None },
+            due : if all(&|t| crate::dates::due_days(&t.due) == due) { Some(first.due) } else { None },
+            done : if all(&|t| t.done == first.done) { Some(first.done) } else { None },
+            priority : if all(&|t| t.priority == first.priority) { Some(first.priority) } else { None },
+        }
+    }
+}
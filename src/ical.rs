@@ -0,0 +1,316 @@
+//! Tasks as iCalendar(RFC 5545) `VTODO`s, so calendar clients can show them
+
+use crate::dates;
+use crate::merge::{self, Import};
+use crate::priority::MAX_PRIORITY;
+use crate::undo::TaskData;
+use cool_organizer::*;
+
+/// Lines longer than this many bytes get folded
+const LINE_LENGTH : usize = 75;
+
+/// A `VTODO` read from a file
+pub struct Todo {
+    /// `None` if the file didn't give it one
+    pub uid : Option<String>,
+    pub task : TaskData,
+}
+
+/// UIDs of `tasks`, in the same order.
+/// Tasks have no id of their own, so it comes from the name and categories, and a task
+/// keeps it as long as those don't change. Tasks which look the same get numbered.
+/// Renaming or moving a task gives it a new UID, so importing a file exported before that adds it again
+pub fn uids(tasks : &[TaskData]) -> Vec<String> {
+    let mut uids : Vec<String> = Vec::new();
+    for task in tasks.iter() {
+        let base = format!("{:016x}@corganizerui", content_hash(task));
+        let mut uid = base.clone();
+        let mut n = 1;
+        while uids.contains(&uid) {
+            n += 1;
+            uid = format!("{}-{}", base, n);
+        }
+        uids.push(uid);
+    }
+    uids
+}
+
+/// FNV-1a, which unlike `std`'s hashers is guaranteed to stay the same between builds
+fn content_hash(task : &TaskData) -> u64 {
+    let content = format!("{}\u{1f}{}\u{1f}{}", task.category, task.sub_category, task.name.trim());
+    content.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// iCalendar's priority goes from 1(highest) to 9(lowest) with 0 being none, ours from 0(none) up to `MAX_PRIORITY`
+fn to_ical_priority(priority : u8) -> u8 {
+    if priority == 0 { 0 } else { 9 - priority.min(MAX_PRIORITY) }
+}
+
+fn from_ical_priority(priority : u8) -> u8 {
+    if priority == 0 || priority > 9 { 0 } else { (9 - priority).min(MAX_PRIORITY) }
+}
+
+/// A calendar with a `VTODO` for every task, `stamp` is when it was made(`YYYYMMDDTHHMMSSZ`)
+pub fn write(tasks : &[TaskData], stamp : &str) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//corganizerui//cool organizer ui//EN".to_string(),
+    ];
+    for (task, uid) in tasks.iter().zip(uids(tasks).iter()) {
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:{}", escape(uid)));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("SUMMARY:{}", escape(&task.name)));
+        let categories : Vec<String> = [&task.category, &task.sub_category].iter()
+            .filter(|c| !c.is_empty())
+            .map(|c| escape(c))
+            .collect();
+        if !categories.is_empty() {
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+        if task.due.is_some() {
+            lines.push(format!("DUE;VALUE=DATE:{}", dates::format_due(&task.due).replace('-', "")));
+        }
+        lines.push(format!("PRIORITY:{}", to_ical_priority(task.priority)));
+        lines.push(format!("STATUS:{}", if task.done { "COMPLETED" } else { "NEEDS-ACTION" }));
+        lines.push("END:VTODO".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::new();
+    for line in lines.iter() {
+        fold(line, &mut ics);
+    }
+    ics
+}
+
+/// Appends `line` to `ics`, broken into lines of at most `LINE_LENGTH` bytes(without splitting a character)
+fn fold(line : &str, ics : &mut String) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LENGTH {
+            // The space starting the next line counts too
+            ics.push_str("\r\n ");
+            length = 1;
+        }
+        ics.push(c);
+        length += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn escape(text : &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Splits a value on the commas which aren't escaped, unescaping the parts
+fn split_unescape(value : &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('N') => parts.last_mut().unwrap().push('\n'),
+                Some(c) => parts.last_mut().unwrap().push(c),
+                None => (),
+            },
+            ',' => parts.push(String::new()),
+            _ => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+fn unescape(value : &str) -> String {
+    // Commas inside a text value are always escaped, so there is only the one part
+    split_unescape(value).join(",")
+}
+
+/// Days since the epoch of a `DATE`(`YYYYMMDD`) or the date part of a `DATE-TIME`
+fn parse_date(value : &str) -> Option<i64> {
+    let date = value.get(..8)?;
+    let year : i64 = date.get(..4)?.parse().ok()?;
+    let month : u32 = date.get(4..6)?.parse().ok()?;
+    let day : u32 = date.get(6..8)?.parse().ok()?;
+    dates::checked_days(year, month, day)
+}
+
+/// Every `VTODO` in `text` which has a summary, anything else in the calendar is ignored
+pub fn parse(text : &str) -> Vec<Todo> {
+    // Unfold first, a line starting with a space or tab carries on the previous one
+    let mut lines : Vec<String> = Vec::new();
+    for line in text.split('\n').map(|line| line.trim_end_matches('\r')) {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut todos = Vec::new();
+    let mut current : Option<(Option<String>, TaskData)> = None;
+    // Components inside the `VTODO`(like a `VALARM`) have properties of their own, which aren't the task's
+    let mut depth = 0;
+    for line in lines.iter() {
+        // `NAME;PARAM=...:value`, the value can have colons of its own
+        let (name, value) = match line.find(':') {
+            Some(i) => (&line[..i], &line[i + 1..]),
+            None => continue,
+        };
+        let name = name.split(';').next().unwrap_or("").to_uppercase();
+
+        match name.as_str() {
+            "BEGIN" if current.is_some() => depth += 1,
+            "BEGIN" if value.eq_ignore_ascii_case("VTODO") => {
+                current = Some((None, TaskData::from_task(&Task::new(""))));
+                depth = 0;
+            }
+            "END" if depth > 0 => depth -= 1,
+            "END" if value.eq_ignore_ascii_case("VTODO") => {
+                if let Some((uid, task)) = current.take() {
+                    if !task.name.trim().is_empty() {
+                        todos.push(Todo { uid, task });
+                    }
+                }
+            }
+            _ => (),
+        }
+        let (uid, task) = match current.as_mut() {
+            Some((uid, task)) if depth == 0 => (uid, task),
+            _ => continue,
+        };
+        match name.as_str() {
+            "UID" => *uid = Some(unescape(value)),
+            "SUMMARY" => task.name = unescape(value),
+            "CATEGORIES" => {
+                let mut categories = split_unescape(value).into_iter().map(|c| c.trim().to_string());
+                if let Some(category) = categories.next().filter(|c| !c.is_empty()) {
+                    task.category = category;
+                }
+                task.sub_category = categories.next().unwrap_or_default();
+            }
+            "DUE" => task.due = parse_date(value).and_then(dates::date_from_days),
+            "PRIORITY" => task.priority = from_ical_priority(value.trim().parse().unwrap_or(0)),
+            "STATUS" => task.done = value.trim().eq_ignore_ascii_case("COMPLETED"),
+            _ => (),
+        }
+    }
+    todos
+}
+
+/// Matches `todos` to the tasks in `existing`, by UID or else by looking the same as one(see `merge::import`)
+pub fn merge(existing : &[TaskData], todos : Vec<Todo>) -> Import {
+    let existing_uids = uids(existing);
+    let incoming = todos.into_iter()
+        .map(|todo| {
+            // Other clients make up their own UIDs for tasks they got from us
+            let index = todo.uid.and_then(|uid| existing_uids.iter().position(|u| *u == uid));
+            (index, todo.task)
+        })
+        .collect();
+    merge::import(existing, incoming)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name : &str, category : &str, sub_category : &str) -> TaskData {
+        let mut task = TaskData::from_task(&Task::new(name));
+        task.category = category.to_string();
+        task.sub_category = sub_category.to_string();
+        task.due = None;
+        task.done = false;
+        task.priority = 0;
+        task
+    }
+
+    #[test]
+    fn folding() {
+        let mut ics = String::new();
+        fold(&"é".repeat(50), &mut ics);
+        let lines : Vec<&str> = ics.split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= LINE_LENGTH));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(lines[2], "");
+
+        let mut ics = String::new();
+        fold("short", &mut ics);
+        assert_eq!(ics, "short\r\n");
+    }
+
+    #[test]
+    fn escaping() {
+        let text = "a,b;c\\d\ne";
+        assert_eq!(escape(text), "a\\,b\\;c\\\\d\\ne");
+        assert_eq!(unescape(&escape(text)), text);
+        assert_eq!(split_unescape("work\\, home,back\\\\end,\\N"), ["work, home", "back\\end", "\n"]);
+    }
+
+    #[test]
+    fn write_parses_back() {
+        let mut a = task(&"long name ".repeat(10), "work, stuff", "back;end");
+        a.priority = 8;
+        a.done = true;
+        a.due = dates::date_from_days(dates::days_from_civil(2024, 2, 29));
+        let b = task("b", "home", "");
+        let tasks = vec![a, b];
+
+        let todos = parse(&write(&tasks, "20240101T000000Z"));
+        assert_eq!(todos.len(), 2);
+        for ((todo, task), uid) in todos.iter().zip(tasks.iter()).zip(uids(&tasks).iter()) {
+            assert!(todo.task.same_as(task));
+            assert_eq!(todo.uid.as_ref(), Some(uid));
+        }
+    }
+
+    #[test]
+    fn nested_components() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VTODO\r\n\
+            UID:1\r\n\
+            SUMMARY:Pay \r\n the rent\r\n\
+            BEGIN:VALARM\r\n\
+            ACTION:DISPLAY\r\n\
+            SUMMARY:Reminder\r\n\
+            END:VALARM\r\n\
+            PRIORITY:1\r\n\
+            END:VTODO\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Not a task\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let todos = parse(ics);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].task.name, "Pay the rent");
+        assert_eq!(todos[0].task.priority, MAX_PRIORITY);
+    }
+
+    #[test]
+    fn merging() {
+        let existing = vec![task("a", "work", ""), task("b", "home", ""), task("b", "home", "")];
+        let uids = uids(&existing);
+        assert_eq!(uids[2], format!("{}-2", uids[1]));
+
+        let mut done = task("b", "home", "");
+        done.done = true;
+        let todos = vec![
+            // Ours, by UID
+            Todo { uid : Some(uids[2].clone()), task : done },
+            Todo { uid : Some(uids[0].clone()), task : task("a", "work", "") },
+            // Another app's UID for a task it got from us
+            Todo { uid : Some("other".to_string()), task : task("b", "home", "") },
+            Todo { uid : None, task : task("c", "home", "") },
+        ];
+        let import = merge(&existing, todos);
+        assert_eq!(import.changed.len(), 1);
+        assert_eq!(import.changed[0].0, 2);
+        assert!(import.changed[0].1.done);
+        assert_eq!(import.added.len(), 1);
+        assert_eq!(import.added[0].name, "c");
+    }
+}
@@ -1,41 +1,391 @@
-use gtk::{Button, Entry, TreeIter, TreeModel, prelude::*};
+use gtk::{Entry, TreeIter, TreeModel, prelude::*};
+use gio::prelude::*;
+use glib::ToVariant;
 use cool_organizer::*;
 use std::rc::{Rc,Weak};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+
+mod agenda;
+mod board;
+mod calendar;
+mod csv;
+mod dates;
+mod filter;
+mod form;
+mod ical;
+mod merge;
+mod priority;
+mod quick_add;
+mod reminders;
+mod report;
+mod restore;
+mod settings;
+mod shortcuts;
+mod storage;
+mod todo_txt;
+mod undo;
+
+use agenda::Bucket;
+use board::{BoardTask, BoardView, Column};
+use calendar::{CalendarTask, CalendarView};
+use filter::{FilterRow, TaskFilter};
+use form::{Common, Touch, Touched};
+use reminders::{Kind, Scheduler, SystemClock, TaskInfo};
+use settings::{Autosave, Grouping, Settings};
+use storage::TaskFile;
+use undo::{Edit, History, TaskData};
+
+// Columns of the tasks list model, category and sub category rows only fill `COL_NAME` and `COL_TASK`
+const COL_NAME : u32 = 0;
+const COL_SUB : u32 = 1;
+/// Days since the epoch, used for sorting by due date
+const COL_DUE : u32 = 2;
+/// What the due column actually shows
+const COL_DUE_TEXT : u32 = 3;
+const COL_PRIO : u32 = 4;
+const COL_DONE : u32 = 5;
+/// Hidden column holding the task's index in `TasksManager::tasks`
+const COL_TASK : u32 = 6;
+/// Hidden, the parent rows only say the category when grouping by it
+const COL_CAT : u32 = 7;
+/// Every column a task row fills, in the order of `task_row_values`
+const TASK_COLUMNS : [u32; 8] = [COL_NAME, COL_SUB, COL_DUE, COL_DUE_TEXT, COL_PRIO, COL_DONE, COL_TASK, COL_CAT];
+/// Value of `COL_TASK` for rows which are not tasks(categories and sub categories)
+const NO_TASK : i64 = -1;
+
+/// Font weights(pango's) the tasks list uses
+const WEIGHT_NORMAL : i32 = 400;
+const WEIGHT_BOLD : i32 = 700;
+
+/// Shown in the window title after the file name
+const APP_TITLE : &str = "Cool Organizer's Beautiful Interface";
+/// What we register recently used files under
+const APP_NAME : &str = "corganizerui";
+/// Identifies the app on the session bus, which is how a second launch finds the first
+const APP_ID : &str = "com.github.RustyStriker.corganizerui";
+/// Drag and drop target for moving tasks around `tasks_list`, the data is the task's index
+const TASK_TARGET : &str = "corganizerui/task";
+/// Milliseconds a field has to stay unchanged before `Autosave::FieldChange` saves it
+const AUTOSAVE_DELAY : u32 = 1000;
+/// Seconds between looking for something to remind about
+const REMINDER_INTERVAL : u32 = 60;
 
 fn main() {
-    gtk::init().expect("couldnt initialize gtk!");
+    let app = gtk::Application::new(Some(APP_ID), gio::ApplicationFlags::HANDLES_COMMAND_LINE)
+        .expect("couldnt initialize gtk!");
+    glib::set_application_name(APP_NAME);
+
+    // Started by the first command line, every later one(a second launch included) goes to the same window
+    let running : RefCell<Option<(UILayout, Rc<RefCell<TasksManager>>)>> = RefCell::new(None);
+    app.connect_command_line(move |app, cmd| {
+        let args : Vec<String> = cmd.get_arguments().iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let mut command = CommandLine::parse(&args);
+        // A second launch might have come from another directory
+        if let (Some(file), Some(cwd)) = (command.file.as_mut(), cmd.get_cwd()) {
+            *file = cwd.join(&*file).to_string_lossy().into_owned();
+        }
+
+        let existing = running.borrow().clone();
+        let (ui, tasks) = match existing {
+            Some((ui, tasks)) => {
+                let weak = Rc::downgrade(&tasks);
+                if let Some(path) = command.file {
+                    if ui.settle_file(&weak, "Discard") {
+                        ui.open_file(&weak, path);
+                    }
+                }
+                ui.main_window.present();
+                (ui, tasks)
+            }
+            None => {
+                let started = start(app, command.file);
+                *running.borrow_mut() = Some(started.clone());
+                started
+            }
+        };
+
+        for text in command.new.iter() {
+            // Nobody would see an error from a second launch, so then it is all just the name
+            let task = match quick_add::parse(text, dates::today()) {
+                Ok(quick) => quick_task(quick),
+                Err(_) => Task::new(text),
+            };
+            ui.add_task(&Rc::downgrade(&tasks), task);
+        }
+        0
+    });
 
-    
+    let args : Vec<String> = std::env::args().collect();
+    std::process::exit(app.run(&args));
+}
 
+/// Builds the window and loads the task file at `path`(`TasksManager::default_path()` if none)
+fn start(app : &gtk::Application, path : Option<String>) -> (UILayout, Rc<RefCell<TasksManager>>) {
     let glade_src = include_str!("../app.glade");
     let builder = gtk::Builder::from_string(glade_src);
-    let ui = get_layout_from_builder(&builder);
+    let ui = get_layout_from_builder(&builder, app);
 
-    ui.main_window.set_title("Cool Organizer's Beautiful Interface");
+    // Colours of the views, taken from the theme
+    let css = gtk::CssProvider::new();
+    css.load_from_data(include_bytes!("../style.css")).expect("style.css is broken");
+    if let Some(screen) = gdk::Screen::get_default() {
+        gtk::StyleContext::add_provider_for_screen(&screen, &css, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+    }
 
-    let tasks = TasksManager::load(&TasksManager::default_path());
+    let path = path.unwrap_or_else(TasksManager::default_path);
+    let (tasks, path) = load_tasks(path);
+    if let Some(path) = &path {
+        add_to_recent(path);
+    }
+    {
+        let mut file = ui.file.borrow_mut();
+        file.path = path;
+        file.synced(&tasks);
+    }
+    ui.update_title();
     let tasks = Rc::new(RefCell::new(tasks));
 
     ui.initialize(Rc::clone(&tasks));
 
-    ui.main_window.connect_destroy(|_| {
-        gtk::main_quit();
-    });
+    // The application keeps running as long as the window is open
+    ui.main_window.set_application(Some(app));
     ui.main_window.show_all();
-    gtk::main();
+    (ui, tasks)
+}
+
+/// What the command line asks for
+#[derive(Default)]
+struct CommandLine {
+    /// `--file <path>`(or `--file=<path>`), another task file than `TasksManager::default_path()`
+    file : Option<String>,
+    /// `--new <task>`(or `--new=<task>`), tasks to add, may be given more than once
+    new : Vec<String>,
+}
+impl CommandLine {
+    /// `args` includes the program name
+    fn parse(args : &[String]) -> CommandLine {
+        let mut command = CommandLine::default();
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--file" {
+                command.file = args.next().cloned();
+            }
+            else if let Some(path) = arg.strip_prefix("--file=") {
+                command.file = Some(path.to_string());
+            }
+            else if arg == "--new" {
+                command.new.extend(args.next().cloned());
+            }
+            else if let Some(task) = arg.strip_prefix("--new=") {
+                command.new.push(task.to_string());
+            }
+        }
+        command
+    }
+}
+
+/// The task a quick add entry describes
+fn quick_task(quick : quick_add::QuickTask) -> Task {
+    let mut task = Task::new(&quick.name);
+    if let Some(category) = quick.category {
+        task.category = category;
+    }
+    if let Some(sub_category) = quick.sub_category {
+        task.sub_category = sub_category;
+    }
+    if let Some(priority) = quick.priority {
+        task.priority = priority;
+    }
+    task.due = quick.due.and_then(dates::date_from_days);
+    task
+}
+
+/// Loads the tasks from `path`, asking what to do whenever that fails.
+/// Returns the tasks and where they should be saved to
+fn load_tasks(path : String) -> (TasksManager, Option<String>) {
+    let mut path = path;
+    loop {
+        let error = match storage::load(&path) {
+            Ok(tasks) => return (tasks, Some(path)),
+            Err(e) => e,
+        };
+        let backup = storage::backups(&path).into_iter().next();
+
+        let dia = gtk::MessageDialog::new(
+            None::<&gtk::Window>,
+            gtk::DialogFlags::MODAL,
+            gtk::MessageType::Error,
+            gtk::ButtonsType::None,
+            &format!("Couldn't load the tasks from:\n\t{}\n{}", path, error)
+        );
+        dia.set_title("Loading Failed");
+        dia.add_button("Retry", gtk::ResponseType::Other(0));
+        dia.add_button("Open Another File...", gtk::ResponseType::Other(1));
+        if let Some(backup) = &backup {
+            dia.add_button(&format!("Open Backup from {}", backup.label()), gtk::ResponseType::Other(2));
+        }
+        dia.add_button("Start Empty", gtk::ResponseType::Other(3));
+        let res = dia.run();
+        dia.hide();
+
+        match res {
+            gtk::ResponseType::Other(0) => (),
+            gtk::ResponseType::Other(1) => {
+                if let Some(other) = choose_file(None, "Open Task File", gtk::FileChooserAction::Open) {
+                    path = other;
+                }
+            }
+            gtk::ResponseType::Other(2) => {
+                // The next save writes the backup's tasks over the broken file
+                if let Some(Ok(tasks)) = backup.map(|backup| storage::load(&backup.path)) {
+                    return (tasks, Some(path));
+                }
+            }
+            // Starting empty(or closing the dialog) leaves the broken file alone
+            _ => return (storage::empty_tasks(), None),
+        }
+    }
+}
+
+/// Puts the task file in the recently used files
+fn add_to_recent(path : &str) {
+    // Recent files want a full uri
+    let path = match std::fs::canonicalize(path) {
+        Ok(path) => path,
+        Err(_) => return,
+    };
+    if let (Some(manager), Ok(uri)) = (gtk::RecentManager::get_default(), glib::filename_to_uri(&path, None)) {
+        manager.add_item(&uri);
+    }
+}
+
+fn show_error(parent : &gtk::Window, title : &str, message : &str) {
+    let dia = gtk::MessageDialog::new(
+        Some(parent),
+        gtk::DialogFlags::DESTROY_WITH_PARENT,
+        gtk::MessageType::Error,
+        gtk::ButtonsType::Ok,
+        message
+    );
+    dia.set_title(title);
+    dia.run();
+    dia.hide();
+}
+
+/// Asks whether to save the changes described by `message`, `Accept` to save,
+/// `Reject` to throw them away and anything else to cancel
+fn ask_save_changes(parent : &gtk::Window, message : &str, discard : &str) -> gtk::ResponseType {
+    let dialog = gtk::MessageDialog::new(
+        Some(parent),
+        gtk::DialogFlags::MODAL,
+        gtk::MessageType::Question,
+        gtk::ButtonsType::None,
+        message,
+    );
+    dialog.set_title("Unsaved Changes");
+    dialog.add_button(discard, gtk::ResponseType::Reject);
+    dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    dialog.add_button("Save", gtk::ResponseType::Accept);
+    dialog.set_default_response(gtk::ResponseType::Accept);
+    let response = dialog.run();
+    dialog.close();
+    response
+}
+
+/// Asks for a line of text, `None` if cancelled or left empty
+fn ask_text(parent : &gtk::Window, title : &str, message : &str) -> Option<String> {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(title),
+        Some(parent),
+        gtk::DialogFlags::MODAL,
+        &[("Cancel", gtk::ResponseType::Cancel), ("OK", gtk::ResponseType::Accept)],
+    );
+    dialog.set_default_response(gtk::ResponseType::Accept);
+    let entry = Entry::new();
+    entry.set_activates_default(true);
+    let content = dialog.get_content_area();
+    content.set_spacing(6);
+    content.add(&gtk::Label::new(Some(message)));
+    content.add(&entry);
+    dialog.show_all();
+
+    let response = dialog.run();
+    let text = entry.get_text().trim().to_string();
+    dialog.close();
+    if response == gtk::ResponseType::Accept && !text.is_empty() {
+        Some(text)
+    }
+    else {
+        None
+    }
+}
+
+/// Asks for a file, `None` if the dialog was cancelled
+fn choose_file(parent : Option<&gtk::Window>, title : &str, action : gtk::FileChooserAction) -> Option<String> {
+    choose_file_named(parent, title, action, None)
+}
+
+/// `choose_file` with `name` filled in to start with, which only saving has room for
+fn choose_file_named(parent : Option<&gtk::Window>, title : &str, action : gtk::FileChooserAction, name : Option<&str>) -> Option<String> {
+    let accept = if action == gtk::FileChooserAction::Save { "Save" } else { "Open" };
+    let dia = gtk::FileChooserDialog::with_buttons(
+        Some(title),
+        parent,
+        action,
+        &[("Cancel", gtk::ResponseType::Cancel), (accept, gtk::ResponseType::Accept)]
+    );
+    dia.set_do_overwrite_confirmation(true);
+    if let (gtk::FileChooserAction::Save, Some(name)) = (action, name) {
+        dia.set_current_name(name);
+    }
+    let res = dia.run();
+    let file = dia.get_filename();
+    dia.hide();
+
+    if res == gtk::ResponseType::Accept {
+        file.map(|f| f.to_string_lossy().into_owned())
+    }
+    else {
+        None
+    }
 }
 
 #[derive(Clone)]
 struct UILayout {
     // General stuff
+    app : gtk::Application,
     main_window : gtk::Window,
-    save : Button,
-    new : Button,
-    delete : Button,
-    remove_done : Button,
+    quick_add : Entry,
+    // File menu
+    menu_new_list : gtk::MenuItem,
+    menu_open : gtk::MenuItem,
+    menu_save_as : gtk::MenuItem,
+    menu_import_csv : gtk::MenuItem,
+    menu_export_csv : gtk::MenuItem,
+    menu_import_ical : gtk::MenuItem,
+    menu_export_ical : gtk::MenuItem,
+    menu_import_todo_txt : gtk::MenuItem,
+    menu_export_todo_txt : gtk::MenuItem,
+    menu_mirror_todo_txt : gtk::CheckMenuItem,
+    menu_export_report : gtk::MenuItem,
+    menu_restore_backup : gtk::MenuItem,
+    recent_menu : gtk::RecentChooserMenu,
+    autosave_off : gtk::RadioMenuItem,
+    autosave_field : gtk::RadioMenuItem,
+    autosave_selection : gtk::RadioMenuItem,
+    autosave_timed : gtk::RadioMenuItem,
+    // Views of the tasks, the tree is the `list` page
+    views : gtk::Stack,
     tasks_list : gtk::TreeView,
+    calendar : CalendarView,
+    board : BoardView,
     task_grid : gtk::Grid,
+    due_label : gtk::Label,
+    prio_label : gtk::Label,
+    save_infobar : gtk::InfoBar,
+    save_error : gtk::Label,
     // Task specific
     name : Entry,
     category : Entry,
@@ -44,64 +394,1418 @@ struct UILayout {
     date : gtk::Calendar,
     done : gtk::CheckButton,
     prio : gtk::SpinButton,
+    // Search bar
+    search : gtk::SearchEntry,
+    filter_hide_done : gtk::ToggleButton,
+    filter_overdue : gtk::ToggleButton,
+    filter_week : gtk::ToggleButton,
+    filter_prio_on : gtk::CheckButton,
+    filter_prio : gtk::SpinButton,
+    grouping : gtk::ComboBoxText,
+    // Models behind `tasks_list`, the store is filtered and the filtered rows are sorted
+    tasks_store : gtk::TreeStore,
+    filter_model : gtk::TreeModelFilter,
+    sort_model : gtk::TreeModelSort,
+    // Not widgets, but everyone wants them
+    settings : Rc<RefCell<Settings>>,
+    task_filter : Rc<RefCell<TaskFilter>>,
+    history : Rc<RefCell<History>>,
+    file : Rc<RefCell<TaskFile>>,
+    /// Watches `file` for changes made by other programs
+    monitor : Rc<RefCell<Option<gio::FileMonitor>>>,
+    /// Watches the todo.txt mirror for changes made by todo.txt tools
+    mirror_monitor : Rc<RefCell<Option<gio::FileMonitor>>>,
+    /// The tasks as they were last written to the todo.txt mirror, a line each
+    mirrored : Rc<RefCell<Option<Vec<TaskData>>>>,
+    // State of the task grid
+    /// Indices of the tasks the grid shows, more than one when bulk editing
+    form_tasks : Rc<RefCell<Vec<usize>>>,
+    /// Fields edited since the grid was filled in or saved
+    form_touched : Rc<Cell<Touched>>,
+    /// Set while we fill in the grid ourselves, so it doesn't count as an edit
+    filling_form : Rc<Cell<bool>>,
+    /// The pending `Autosave::FieldChange` save
+    autosave_pending : Rc<RefCell<Option<glib::SourceId>>>,
+    reminders : Rc<RefCell<Scheduler>>,
+    /// What Hide Done was before the agenda turned it on, it goes back to that when grouping by category again
+    hide_done_outside_agenda : Rc<Cell<Option<bool>>>,
 }
 impl UILayout {
     fn initialize(&self, tasks : Rc<RefCell<TasksManager>>) {
         // Disable the task window
         self.disable_task();
 
+        // Restore the last sort order
+        {
+            let settings = self.settings.borrow();
+            if let Some(col) = settings.sort_column {
+                let order = if settings.sort_ascending { gtk::SortType::Ascending } else { gtk::SortType::Descending };
+                self.sort_model.set_sort_column_id(gtk::SortColumn::Index(col), order);
+            }
+        }
+        // And remember it whenever a header is clicked
+        let settings = Rc::clone(&self.settings);
+        self.sort_model.connect_sort_column_changed(move |model| {
+            let mut settings = settings.borrow_mut();
+            match model.get_sort_column_id() {
+                Some((gtk::SortColumn::Index(col), order)) => {
+                    settings.sort_column = Some(col);
+                    settings.sort_ascending = order == gtk::SortType::Ascending;
+                }
+                _ => settings.sort_column = None,
+            }
+            let _ = settings.save();
+        });
 
+        let task_filter = Rc::clone(&self.task_filter);
+        self.filter_model.set_visible_func(move |model, iter| {
+            let task_filter = task_filter.borrow();
+            task_filter.is_empty() || is_row_visible(model, iter, &task_filter, dates::today())
+        });
 
+        self.tasks_list.set_model(Some(&self.sort_model));
         self.update_tasks_list(&tasks.borrow());
-        let renderer = gtk::CellRendererText::new();
+
+        self.add_text_column("Name", COL_NAME, COL_NAME);
+        self.add_text_column("Sub Category", COL_SUB, COL_SUB);
+        self.add_text_column("Due", COL_DUE_TEXT, COL_DUE);
+        self.add_text_column("Priority", COL_PRIO, COL_PRIO);
+
+        let renderer = gtk::CellRendererToggle::new();
         let col = gtk::TreeViewColumn::new();
+        col.set_title("Done");
+        col.pack_start(&renderer, false);
+        col.add_attribute(&renderer, "active", COL_DONE as i32);
+        col.set_sort_column_id(COL_DONE as i32);
+        // Categories can't be done
+        TreeViewColumnExt::set_cell_data_func(&col, &renderer, Some(Box::new(|_, cell, model, iter| {
+            cell.set_visible(get_task_index(model, iter).is_some());
+        })));
+        self.tasks_list.append_column(&col);
+
+        // Toggling done straight from the list
+        let clone = self.clone();
+        let tclone = Rc::downgrade(&tasks);
+        renderer.connect_toggled(move |_, path| {
+            let iter = match clone.sort_model.get_iter(&path) {
+                Some(iter) => clone.to_store_iter(&iter),
+                None => return,
+            };
+            let index = match get_task_index(clone.tasks_store.upcast_ref(), &iter) {
+                Some(i) => i,
+                None => return,
+            };
+            if let Some(t) = tclone.upgrade() {
+                let mut t = t.borrow_mut();
+                let (done, values) = match t.tasks.get_mut(index) {
+                    Some(task) => {
+                        let before = TaskData::from_task(task);
+                        task.done = !task.done;
+                        clone.push_edit(Edit::Change { index, before, after : TaskData::from_task(task) });
+                        (task.done, task_row_values(index, task))
+                    }
+                    None => return,
+                };
+                clone.save_tasks(&t);
+                drop(t);
+
+                set_task_row(&clone.tasks_store, &iter, &values);
+                // Keep the task grid in sync if it is showing this task
+                if *clone.form_tasks.borrow() == [index] {
+                    clone.filling_form.set(true);
+                    clone.done.set_active(done);
+                    clone.filling_form.set(false);
+                }
+                // Hide Done might hide it now, and its group with it
+                clone.refilter();
+            }
+        });
         
+        self.clone().connect_ui(Rc::downgrade(&tasks));
+
+    }
+
+    /// Appends a sortable text column showing `column` and sorting by `sort_column`
+    fn add_text_column(&self, title : &str, column : u32, sort_column : u32) {
+        let renderer = gtk::CellRendererText::new();
+        let col = gtk::TreeViewColumn::new();
+
+        col.set_title(title);
+        col.set_resizable(true);
         col.pack_start(&renderer, true);
-        col.add_attribute(&renderer, "text", 0);
+        col.add_attribute(&renderer, "text", column as i32);
+        col.set_sort_column_id(sort_column as i32);
+        let clone = self.clone();
+        TreeViewColumnExt::set_cell_data_func(&col, &renderer, Some(Box::new(move |_, cell, model, iter| {
+            if let Some(cell) = cell.downcast_ref::<gtk::CellRendererText>() {
+                clone.style_cell(cell, model, iter, column);
+            }
+        })));
 
         self.tasks_list.append_column(&col);
-        
-        self.clone().connect_ui(Rc::downgrade(&tasks));
+    }
+
+    /// Overdue tasks are red, ones due today bold and done ones struck through and dimmed.
+    /// The priority gets a coloured badge and group rows count their tasks.
+    /// The colours are defined in style.css, on top of the theme's
+    fn style_cell(&self, cell : &gtk::CellRendererText, model : &TreeModel, iter : &TreeIter, column : u32) {
+        let style = self.tasks_list.get_style_context();
+        let today = dates::today();
+
+        if get_task_index(model, iter).is_none() {
+            cell.set_property_strikethrough(false);
+            cell.set_property_weight(WEIGHT_NORMAL);
+            cell.set_property_foreground_set(false);
+            match column {
+                COL_NAME => {
+                    let (open, overdue) = count_tasks(model, iter, today);
+                    let name = get_row_text(model, iter, COL_NAME);
+                    let title = if overdue > 0 {
+                        format!("{} ({} open / {} overdue)", name, open, overdue)
+                    }
+                    else {
+                        format!("{} ({} open)", name, open)
+                    };
+                    cell.set_property_text(Some(&title));
+                }
+                COL_PRIO => cell.set_property_text(Some("")),
+                _ => (),
+            }
+            return;
+        }
+
+        let due = model.get_value(iter, COL_DUE as i32).get_some().unwrap_or(dates::NO_DUE);
+        let done = model.get_value(iter, COL_DONE as i32).get_some().unwrap_or(false);
+        cell.set_property_strikethrough(done);
+        cell.set_property_weight(if !done && due == today { WEIGHT_BOLD } else { WEIGHT_NORMAL });
+        let foreground = if done {
+            style.lookup_color("task_done_color")
+        }
+        else if due < today {
+            style.lookup_color("task_overdue_color")
+        }
+        else {
+            None
+        };
+        match foreground {
+            Some(color) => cell.set_property_foreground_rgba(Some(&color)),
+            None => cell.set_property_foreground_set(false),
+        }
+
+        if column == COL_PRIO {
+            let priority : u32 = model.get_value(iter, COL_PRIO as i32).get_some().unwrap_or(0);
+            // `priority-high` is `priority_high_color`
+            let badge = format!("{}_color", priority::priority_class(priority).replace('-', "_"));
+            if let (Some(background), Some(text)) = (style.lookup_color(&badge), style.lookup_color("badge_text_color")) {
+                cell.set_property_markup(Some(&format!(
+                    "<span background=\"{}\" foreground=\"{}\"> {} </span>",
+                    color_hex(&background), color_hex(&text), priority,
+                )));
+            }
+        }
+    }
 
+    /// Converts an iter of the model shown by `tasks_list` into one of `tasks_store`
+    fn to_store_iter(&self, iter : &TreeIter) -> TreeIter {
+        let filter_iter = self.sort_model.convert_iter_to_child_iter(iter);
+        self.filter_model.convert_iter_to_child_iter(&filter_iter)
     }
 
-    fn update_tasks_list(&self, tasks : &TasksManager) {
-        let model = gtk::TreeStore::new(&[glib::Type::String]);
+    /// Converts a `tasks_store` iter into one `tasks_list` can use, `None` if it is filtered out
+    fn to_view_iter(&self, iter : &TreeIter) -> Option<TreeIter> {
+        let filter_iter = self.filter_model.convert_child_iter_to_iter(iter)?;
+        self.sort_model.convert_child_iter_to_iter(&filter_iter)
+    }
+
+    /// The selected tasks as `tasks_store` iters and their index in `TasksManager::tasks`,
+    /// selected category rows are left out
+    fn get_selected_tasks(&self) -> Vec<(TreeIter, usize)> {
+        let (paths, model) = self.tasks_list.get_selection().get_selected_rows();
+        paths.iter()
+            .filter_map(|path| model.get_iter(path))
+            .filter_map(|iter| {
+                let iter = self.to_store_iter(&iter);
+                let index = get_task_index(self.tasks_store.upcast_ref(), &iter)?;
+                Some((iter, index))
+            })
+            .collect()
+    }
+
+    /// Indices of the selected tasks, lowest first
+    fn get_selected_indices(&self) -> Vec<usize> {
+        let mut indices : Vec<usize> = self.get_selected_tasks().into_iter().map(|(_, index)| index).collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Category and sub category of the first selected row, be it a task or a group(of categories only)
+    fn selected_placement(&self) -> Option<(String, String)> {
+        let (paths, model) = self.tasks_list.get_selection().get_selected_rows();
+        let iter = model.get_iter(paths.first()?)?;
+        let iter = self.to_store_iter(&iter);
+        let store = self.tasks_store.upcast_ref();
+        match get_task_index(store, &iter) {
+            Some(_) => Some((get_row_text(store, &iter, COL_CAT), get_row_text(store, &iter, COL_SUB))),
+            None if self.settings.borrow().grouping == Grouping::Category => Some(get_group_placement(store, &iter)),
+            None => None,
+        }
+    }
+
+    /// Names of the group rows `task` goes under with the current grouping, from the top level down
+    fn task_groups(&self, task : &Task) -> Vec<String> {
+        match self.settings.borrow().grouping {
+            Grouping::Category if task.sub_category.is_empty() => vec![task.category.clone()],
+            Grouping::Category => vec![task.category.clone(), task.sub_category.clone()],
+            Grouping::Agenda => vec![task_bucket(task, dates::today()).title().to_string()],
+        }
+    }
+
+    /// Groups the tasks list by `grouping` from now on, and remembers it
+    fn set_grouping(&self, tasks : &Weak<RefCell<TasksManager>>, grouping : Grouping) {
+        if self.settings.borrow().grouping == grouping {
+            return;
+        }
+        if !self.settle_form(tasks) {
+            // Back to what it was, which lands in the early return above
+            self.grouping.set_active_id(Some(self.settings.borrow().grouping.as_str()));
+            return;
+        }
+        {
+            let mut settings = self.settings.borrow_mut();
+            settings.grouping = grouping;
+            let _ = settings.save();
+        }
+        self.enable_tree_dnd(grouping == Grouping::Category);
+        // Done tasks are only in the way of the agenda, unless asked for
+        if grouping == Grouping::Agenda {
+            self.hide_done_in_agenda();
+        }
+        else if let Some(hide) = self.hide_done_outside_agenda.take() {
+            self.filter_hide_done.set_active(hide);
+        }
+
+        let selected = self.get_selected_indices();
+        if let Some(t) = tasks.upgrade() {
+            self.update_tasks_list(&t.borrow());
+        }
+        self.select_tasks(&selected);
+    }
+
+    /// Turns Hide Done on for the agenda, remembering what it was
+    fn hide_done_in_agenda(&self) {
+        self.hide_done_outside_agenda.set(Some(self.filter_hide_done.get_active()));
+        self.filter_hide_done.set_active(true);
+    }
+
+    /// Dragging tasks around the tree moves them between categories, which means nothing when grouping by something else
+    fn enable_tree_dnd(&self, enable : bool) {
+        if enable {
+            let targets = [gtk::TargetEntry::new(TASK_TARGET, gtk::TargetFlags::SAME_WIDGET, 0)];
+            self.tasks_list.drag_source_set(gdk::ModifierType::BUTTON1_MASK, &targets, gdk::DragAction::MOVE);
+            self.tasks_list.drag_dest_set(gtk::DestDefaults::MOTION | gtk::DestDefaults::DROP, &targets, gdk::DragAction::MOVE);
+        }
+        else {
+            self.tasks_list.drag_source_unset();
+            self.tasks_list.drag_dest_unset();
+        }
+    }
+
+    /// Adds a `tasks_store` row to the selection, unless the filter hides it
+    fn select_row(&self, iter : &TreeIter) {
+        if let Some(iter) = self.to_view_iter(iter) {
+            if let Some(path) = self.sort_model.get_path(&iter) {
+                self.tasks_list.expand_to_path(&path);
+            }
+            self.tasks_list.get_selection().select_iter(&iter);
+        }
+    }
+
+    /// Selects the row of the task at `index` in `TasksManager::tasks`, and nothing else
+    fn select_task(&self, index : usize) {
+        self.select_tasks(&[index]);
+    }
+
+    /// Selects the rows of the tasks at `indices`, and nothing else
+    fn select_tasks(&self, indices : &[usize]) {
+        self.tasks_list.get_selection().unselect_all();
+        for index in indices.iter() {
+            if let Some(iter) = find_task_row(&self.tasks_store, *index) {
+                self.select_row(&iter);
+            }
+        }
+    }
+
+    /// Records an edit which was just made to the tasks
+    fn push_edit(&self, edit : Edit) {
+        self.history.borrow_mut().push(edit);
+        self.update_history_buttons();
+    }
+
+    fn update_history_buttons(&self) {
+        let history = self.history.borrow();
+        self.set_action_enabled("undo", history.can_undo());
+        self.set_action_enabled("redo", history.can_redo());
+    }
+
+    /// Appends `task` to the tasks and the tree, then saves. Returns the index it got
+    fn add_task(&self, tasks : &Weak<RefCell<TasksManager>>, task : Task) -> Option<usize> {
+        let t = tasks.upgrade()?;
+        let mut t = t.borrow_mut();
+        t.add_task(task);
+        let index = t.tasks.len() - 1;
+        self.push_edit(Edit::Add { index, task : TaskData::from_task(&t.tasks[index]) });
+        self.save_tasks(&t);
+
+        let task = &t.tasks[index];
+        let parent = get_or_insert_group(&self.tasks_store, &self.task_groups(task));
+        let _ = insert_task_row(&self.tasks_store, Some(&parent), index, task);
+        drop(t);
+        // A freshly made category won't show up through the filter until refiltering
+        self.refilter();
+        Some(index)
+    }
+
+    /// Changes the tasks at the indices in `import.changed`, removes those in `import.removed`
+    /// and appends `import.added`, as a single undo step, then saves and fills the tree again
+    fn import_tasks(&self, tasks : &Weak<RefCell<TasksManager>>, import : merge::Import) {
+        let t = match tasks.upgrade() {
+            Some(t) => t,
+            None => return,
+        };
+        let mut t = t.borrow_mut();
+        let mut edits = Vec::new();
+        for (index, after) in import.changed.into_iter() {
+            if let Some(task) = t.tasks.get_mut(index) {
+                let before = TaskData::from_task(task);
+                after.apply(task);
+                edits.push(Edit::Change { index, before, after });
+            }
+        }
+        let mut removed : Vec<usize> = import.removed.into_iter().filter(|index| *index < t.tasks.len()).collect();
+        removed.sort_unstable();
+        removed.dedup();
+        if !removed.is_empty() {
+            let removed : Vec<(usize, TaskData)> = removed.iter().map(|index| (*index, TaskData::from_task(&t.tasks[*index]))).collect();
+            // Back to front so the indices before are still correct
+            for (index, _) in removed.iter().rev() {
+                t.tasks.remove(*index);
+            }
+            edits.push(Edit::RemoveMany { tasks : removed });
+        }
+        for task in import.added.iter() {
+            t.add_task(task.to_task());
+            edits.push(Edit::Add { index : t.tasks.len() - 1, task : task.clone() });
+        }
+        if let Some(edit) = Edit::batch(edits) {
+            self.push_edit(edit);
+        }
+        self.save_tasks(&t);
+        self.update_tasks_list(&t);
+    }
+
+    /// Puts back tasks from a backup, as a single undo step
+    fn restore_tasks(&self, tasks : &Weak<RefCell<TasksManager>>, restore : restore::Restore) {
+        let t = match tasks.upgrade() {
+            Some(t) => t,
+            None => return,
+        };
+        let existing : Vec<TaskData> = t.borrow().tasks.iter().map(TaskData::from_task).collect();
+        match restore {
+            restore::Restore::All(backup) => {
+                {
+                    let mut t = t.borrow_mut();
+                    let mut edits = Vec::new();
+                    if !existing.is_empty() {
+                        edits.push(Edit::RemoveMany { tasks : existing.into_iter().enumerate().collect() });
+                    }
+                    t.tasks.clear();
+                    for (index, task) in backup.into_iter().enumerate() {
+                        t.tasks.push(task.to_task());
+                        edits.push(Edit::Add { index, task });
+                    }
+                    if let Some(edit) = Edit::batch(edits) {
+                        self.push_edit(edit);
+                    }
+                    self.save_tasks(&t);
+                    self.update_tasks_list(&t);
+                }
+            }
+            // Tasks which are still around(even renamed or moved since) get their old fields back, the rest are added again
+            restore::Restore::Selected { backup, indices } => {
+                let aligned = merge::align(&backup, &existing);
+                let mut import = merge::Import::default();
+                for index in indices.into_iter().filter(|index| *index < backup.len()) {
+                    let task = backup[index].clone();
+                    match aligned[index] {
+                        Some(now) if existing[now].same_as(&task) => (),
+                        Some(now) => import.changed.push((now, task)),
+                        None => import.added.push(task),
+                    }
+                }
+                self.import_tasks(tasks, import);
+            }
+        }
+    }
+
+    /// Highlights the part of the quick add entry `error` is about, the message goes in the icon's tooltip
+    fn show_quick_add_error(&self, text : &str, error : &quick_add::ParseError) {
+        let entry = &self.quick_add;
+        entry.get_style_context().add_class("error");
+        entry.set_icon_from_icon_name(gtk::EntryIconPosition::Secondary, Some("dialog-error-symbolic"));
+        entry.set_icon_tooltip_text(gtk::EntryIconPosition::Secondary, Some(&error.message));
+        // The entry counts characters, not bytes
+        let start = text[..error.start].chars().count() as i32;
+        let end = text[..error.end].chars().count() as i32;
+        entry.grab_focus();
+        entry.select_region(start, end);
+    }
+
+    /// Moves the task at `from` into `category`/`sub_category` and to position `at`
+    /// (counted before it is taken out), then saves. `false` if nothing changed
+    fn move_task(&self, tasks : &Weak<RefCell<TasksManager>>, from : usize, at : usize, category : String, sub_category : String) -> bool {
+        if !self.settle_form(tasks) {
+            return false;
+        }
+        let t = match tasks.upgrade() {
+            Some(t) => t,
+            None => return false,
+        };
+        let mut t = t.borrow_mut();
+        if from >= t.tasks.len() {
+            return false;
+        }
+        // Taking the task out moves everything after it one slot back
+        let to = if at > from { at - 1 } else { at };
+        let to = to.min(t.tasks.len() - 1);
+        let before = TaskData::from_task(&t.tasks[from]);
+        if to == from && before.category == category && before.sub_category == sub_category {
+            return false;
+        }
+
+        let mut task = t.tasks.remove(from);
+        task.category = category;
+        task.sub_category = sub_category;
+        let after = TaskData::from_task(&task);
+        t.tasks.insert(to, task);
+        self.push_edit(Edit::Move { from, to, before, after });
+
+        self.save_tasks(&t);
+        self.update_tasks_list(&t);
+        drop(t);
+        self.select_task(to);
+        true
+    }
+
+    /// Changes every selected task with `change`, as a single step in the history
+    fn change_selected(&self, tasks : &Weak<RefCell<TasksManager>>, change : impl Fn(&mut TaskData)) {
+        if !self.settle_form(tasks) {
+            return;
+        }
+        self.change_tasks(tasks, &self.get_selected_indices(), change);
+    }
+
+    /// Changes the tasks at `indices` with `change`, as a single step in the history.
+    /// The task grid is filled in again afterwards, so settle it first
+    fn change_tasks(&self, tasks : &Weak<RefCell<TasksManager>>, indices : &[usize], change : impl Fn(&mut TaskData)) {
+        let t = match tasks.upgrade() {
+            Some(t) => t,
+            None => return,
+        };
+        let mut t = t.borrow_mut();
+
+        let mut edits = Vec::new();
+        let mut rows = Vec::new();
+        for index in indices.iter().copied() {
+            let task = match t.tasks.get_mut(index) {
+                Some(task) => task,
+                None => continue,
+            };
+            let before = TaskData::from_task(task);
+            let mut after = before.clone();
+            change(&mut after);
+            if after.same_as(&before) {
+                continue;
+            }
+            after.apply(task);
+            rows.push((index, task_row_values(index, task)));
+            edits.push(Edit::Change { index, before, after });
+        }
+        match Edit::batch(edits) {
+            Some(edit) => self.push_edit(edit),
+            None => return,
+        }
 
-        let categories = tasks.get_categories();
+        self.save_tasks(&t);
+        let shown = self.form_tasks.borrow().clone();
+        self.update_task(shown, &t);
+        drop(t);
+        for (index, values) in rows.iter() {
+            if let Some(iter) = find_task_row(&self.tasks_store, *index) {
+                set_task_row(&self.tasks_store, &iter, values);
+            }
+        }
+        // The changes might not pass the filter anymore
+        self.refilter();
+    }
+
+    /// Adds an application action, buttons and menu items pick it up through their `action_name`
+    fn add_action(&self, name : &str, activate : impl Fn() + 'static) {
+        let action = gio::SimpleAction::new(name, None);
+        action.connect_activate(move |_, _| activate());
+        self.app.add_action(&action);
+    }
+
+    /// Enables(or disables) an application action, along with the buttons using it
+    fn set_action_enabled(&self, name : &str, enabled : bool) {
+        let action = self.app.lookup_action(name)
+            .and_then(|action| action.downcast::<gio::SimpleAction>().ok());
+        if let Some(action) = action {
+            action.set_enabled(enabled);
+        }
+    }
+
+    /// Adds the actions only reachable from the keyboard, and binds the keys of every shortcut
+    fn connect_shortcuts(&self, tasks : &Weak<RefCell<TasksManager>>) {
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.add_action("toggle-done", move || {
+            // Undone if they are all done already, done otherwise
+            let all_done = match tclone.upgrade() {
+                Some(t) => {
+                    let t = t.borrow();
+                    clone.get_selected_indices().iter().all(|index| t.tasks.get(*index).is_some_and(|task| task.done))
+                }
+                None => return,
+            };
+            clone.change_selected(&tclone, |task| task.done = !all_done);
+        });
+        let prio = self.prio.get_adjustment();
+        let (lowest, highest) = (prio.get_lower() as u8, prio.get_upper() as u8);
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.add_action("raise-priority", move || {
+            clone.change_selected(&tclone, |task| task.priority = task.priority.saturating_add(1).min(highest));
+        });
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.add_action("lower-priority", move || {
+            clone.change_selected(&tclone, |task| task.priority = task.priority.saturating_sub(1).max(lowest));
+        });
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.add_action("clear-due", move || clone.change_selected(&tclone, |task| task.due = None));
 
-        for cat in categories.iter() {
-            let parent = model.insert_with_values(None, None, &[0], &[cat]);
+        let search = self.search.clone();
+        self.add_action("search", move || search.grab_focus());
+        let quick_add = self.quick_add.clone();
+        self.add_action("quick-add", move || quick_add.grab_focus());
+        let clone = self.clone();
+        self.add_action("shortcuts", move || clone.show_shortcuts());
 
-            for t in tasks.tasks.iter().filter(|t| t.category == cat.as_str()) {
-                let _ = model.insert_with_values(Some(&parent),None,&[0], &[&t.formatted(true)]);
+        // Bound through an `AccelGroup` since its callbacks can pass on a key
+        let accel = gtk::AccelGroup::new();
+        self.main_window.add_accel_group(&accel);
+        for (shortcut, binding) in shortcuts::bindings(&self.settings.borrow().shortcuts) {
+            let (key, mods) = gtk::accelerator_parse(&binding);
+            // Unbound, or not an accelerator at all
+            if key == 0 {
+                continue;
             }
+            let list = self.tasks_list.clone();
+            let app = self.app.clone();
+            let action = shortcut.action;
+            accel.connect_accel_group(key, mods, gtk::AccelFlags::VISIBLE, move |_, _, _, _| {
+                // Keys without modifiers are for typing, except in the tasks list
+                if mods.is_empty() && !list.has_focus() {
+                    return false;
+                }
+                app.activate_action(action, None);
+                true
+            });
         }
+    }
 
-        self.tasks_list.set_model(Some(&model));
+    fn show_shortcuts(&self) {
+        let bindings = shortcuts::bindings(&self.settings.borrow().shortcuts);
+        let builder = gtk::Builder::from_string(&shortcuts::window_xml(&bindings));
+        let window : gtk::ShortcutsWindow = builder.get_object("shortcuts_window").expect("shortcuts_window is missing");
+        window.set_transient_for(Some(&self.main_window));
+        window.show_all();
+    }
+
+    /// Undoes(or redoes) the last edit, then brings the tree and task grid back in line
+    fn step_history(&self, tasks : &Weak<RefCell<TasksManager>>, undo : bool) {
+        if !self.settle_form(tasks) {
+            return;
+        }
+        if let Some(t) = tasks.upgrade() {
+            let mut t = t.borrow_mut();
+            let select = {
+                let mut history = self.history.borrow_mut();
+                if undo { history.undo(&mut t) } else { history.redo(&mut t) }
+            };
+            let select = match select {
+                Some(select) => select,
+                None => return,
+            };
+
+            self.save_tasks(&t);
+            self.update_tasks_list(&t);
+            // Selecting a task borrows the tasks again
+            drop(t);
+
+            self.update_history_buttons();
+            match select {
+                Some(index) => self.select_task(index),
+                None => self.disable_task(),
+            }
+        }
+    }
+
+    /// Sends a notification for everything the scheduler thinks is worth a reminder
+    fn check_reminders(&self, tasks : &Weak<RefCell<TasksManager>>) {
+        let config = {
+            let settings = self.settings.borrow();
+            if !settings.reminders {
+                return;
+            }
+            reminders::Config {
+                lead_days : settings.reminder_lead_days,
+                quiet_start : settings.quiet_start,
+                quiet_end : settings.quiet_end,
+            }
+        };
+        let t = match tasks.upgrade() {
+            Some(t) => t,
+            None => return,
+        };
+        let t = t.borrow();
+        let infos : Vec<TaskInfo> = t.tasks.iter()
+            .enumerate()
+            .map(|(index, task)| TaskInfo { index, name : &task.name, due : dates::due_days(&task.due), done : task.done })
+            .collect();
+        let due = self.reminders.borrow_mut().check(&SystemClock, &config, &infos);
+
+        let today = dates::today();
+        for reminder in due.iter() {
+            let title = match reminder.kind {
+                Kind::DueSoon if reminder.due == today => "Due today".to_string(),
+                Kind::DueSoon if reminder.due == today + 1 => "Due tomorrow".to_string(),
+                Kind::DueSoon => format!("Due {}", dates::format_due(&dates::date_from_days(reminder.due))),
+                Kind::Overdue => "Overdue".to_string(),
+            };
+            let notification = gio::Notification::new(&title);
+            notification.set_body(Some(&reminder.name));
+            // Tasks move around in the meantime, the name helps finding it again
+            let target = format!("{}:{}", reminder.index, reminder.name).to_variant();
+            notification.add_button_with_target_value("Mark done", "app.reminder-done", Some(&target));
+            notification.add_button_with_target_value("Snooze 1 day", "app.reminder-snooze", Some(&target));
+            self.app.send_notification(Some(&format!("reminder:{}:{}", reminder.index, reminder.name)), &notification);
+        }
+    }
+
+    /// Adds the actions behind the buttons of a reminder, they get the reminder's `index:name` target
+    fn connect_reminders(&self, tasks : &Weak<RefCell<TasksManager>>) {
+        for (name, snooze) in [("reminder-done", false), ("reminder-snooze", true)].iter() {
+            let action = gio::SimpleAction::new(name, Some(glib::VariantTy::new("s").expect("not a variant type?!?")));
+            let clone = self.clone();
+            let tclone = tasks.clone();
+            let snooze = *snooze;
+            action.connect_activate(move |_, target| {
+                let target = match target.and_then(|target| target.get_str()) {
+                    Some(target) => target.to_string(),
+                    None => return,
+                };
+                clone.app.withdraw_notification(&format!("reminder:{}", target));
+                if !clone.settle_form(&tclone) {
+                    return;
+                }
+                let index = match tclone.upgrade().and_then(|t| find_reminded_task(&t.borrow(), &target)) {
+                    Some(index) => index,
+                    None => return,
+                };
+                if snooze {
+                    // A day from now, or from the due date if that is still ahead
+                    let today = dates::today();
+                    clone.change_tasks(&tclone, &[index], |task| {
+                        let due = dates::due_days(&task.due);
+                        let from = if due == dates::NO_DUE { today } else { due.max(today) };
+                        task.due = dates::date_from_days(from + 1).or(task.due);
+                    });
+                }
+                else {
+                    clone.change_tasks(&tclone, &[index], |task| task.done = true);
+                }
+            });
+            self.app.add_action(&action);
+        }
+
+        self.check_reminders(tasks);
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        glib::timeout_add_seconds_local(REMINDER_INTERVAL, move || {
+            clone.check_reminders(&tclone);
+            glib::Continue(true)
+        });
+    }
+
+    /// Whether the task grid or the tasks have changes which aren't on the disk
+    fn is_dirty(&self) -> bool {
+        self.form_dirty() || self.file.borrow().dirty
+    }
+
+    /// Whether the task grid was edited since it was filled in or saved
+    fn form_dirty(&self) -> bool {
+        self.form_touched.get().any()
+    }
+
+    /// The title gets a `*` while anything is unsaved, which is also the only time Save does something
+    fn update_title(&self) {
+        let name = self.file.borrow().display_name();
+        let mark = if self.is_dirty() { "*" } else { "" };
+        self.main_window.set_title(&format!("{}{} - {}", mark, name, APP_TITLE));
+        self.set_action_enabled("save", self.is_dirty());
+    }
+
+    /// Swaps the shown tasks for `new`, which lives at `path`
+    fn replace_tasks(&self, tasks : &Weak<RefCell<TasksManager>>, new : TasksManager, path : String) {
+        if let Some(t) = tasks.upgrade() {
+            let mut t = t.borrow_mut();
+            *t = new;
+            {
+                let mut file = self.file.borrow_mut();
+                file.path = Some(path.clone());
+                file.synced(&t);
+            }
+            self.save_infobar.hide();
+            // The indices in the history mean nothing for another list
+            self.history.borrow_mut().clear();
+            self.update_history_buttons();
+
+            self.update_tasks_list(&t);
+            drop(t);
+            self.disable_task();
+            self.update_title();
+            add_to_recent(&path);
+            self.watch_file(tasks);
+        }
+    }
+
+    /// Starts watching the current task file, instead of whatever was watched before
+    fn watch_file(&self, tasks : &Weak<RefCell<TasksManager>>) {
+        if let Some(old) = self.monitor.borrow_mut().take() {
+            old.cancel();
+        }
+        let path = match self.file.borrow().path.clone() {
+            Some(path) => path,
+            None => return,
+        };
+        // Not being able to watch is no reason to bother the user, the file just won't reload
+        let monitor = match gio::File::new_for_path(&path).monitor_file(gio::FileMonitorFlags::NONE, None::<&gio::Cancellable>) {
+            Ok(monitor) => monitor,
+            Err(_) => return,
+        };
+
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        monitor.connect_changed(move |_, _, _, event| {
+            match event {
+                // Editors either write in place or replace the file
+                gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created => clone.reload_changed_file(&tclone),
+                _ => (),
+            }
+        });
+        *self.monitor.borrow_mut() = Some(monitor);
+    }
+
+    /// Picks up the task file after it changed on the disk,
+    /// asking what to do with our changes if there are any that aren't saved
+    fn reload_changed_file(&self, tasks : &Weak<RefCell<TasksManager>>) {
+        let path = {
+            let mut file = self.file.borrow_mut();
+            let path = match &file.path {
+                Some(path) => path.clone(),
+                None => return,
+            };
+            // A missing file would load as an empty list, wait for it to come back instead
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(_) => return,
+            };
+            // Our own save, or a change we already dealt with
+            if file.known_content.as_ref() == Some(&content) {
+                return;
+            }
+            file.known_content = Some(content);
+            path
+        };
+        let theirs = match storage::load(&path) {
+            Ok(theirs) => theirs,
+            // Most likely caught halfway through being written, the next change will bring the rest
+            Err(_) => {
+                self.file.borrow_mut().known_content = None;
+                return;
+            }
+        };
+        let t = match tasks.upgrade() {
+            Some(t) => t,
+            None => return,
+        };
+
+        if !self.is_dirty() {
+            self.show_reloaded(&t, theirs);
+            self.file.borrow_mut().synced(&t.borrow());
+            return;
+        }
+
+        let dialog = gtk::MessageDialog::new(
+            Some(&self.main_window),
+            gtk::DialogFlags::MODAL,
+            gtk::MessageType::Question,
+            gtk::ButtonsType::None,
+            &format!("{} was changed by another program, but you have changes which aren't saved.", self.file.borrow().display_name()),
+        );
+        dialog.set_title("Task File Changed");
+        dialog.add_button("Keep Mine", gtk::ResponseType::Other(0));
+        dialog.add_button("Take Theirs", gtk::ResponseType::Other(1));
+        dialog.add_button("Merge", gtk::ResponseType::Other(2));
+        dialog.set_default_response(gtk::ResponseType::Other(2));
+        let response = dialog.run();
+        dialog.close();
+
+        match response {
+            // Ours go over theirs
+            gtk::ResponseType::Other(0) => self.save_all(tasks),
+            gtk::ResponseType::Other(1) => {
+                self.show_reloaded(&t, theirs);
+                self.file.borrow_mut().synced(&t.borrow());
+            }
+            // Closing the dialog merges too, it loses nothing
+            _ => {
+                // Saving the grid first overwrites theirs on the disk, but we have them right here
+                let base = self.file.borrow().base.clone();
+                if self.form_dirty() {
+                    self.apply_form(tasks);
+                }
+                let ours : Vec<TaskData> = t.borrow().tasks.iter().map(TaskData::from_task).collect();
+                let merged = {
+                    let theirs : Vec<TaskData> = theirs.tasks.iter().map(TaskData::from_task).collect();
+                    merge::merge(&base, &ours, &theirs)
+                };
+                // What's on the disk now is the base for any later merge, until the merge is saved
+                self.file.borrow_mut().synced(&theirs);
+                let mut new = theirs;
+                new.tasks = merged.iter().map(TaskData::to_task).collect();
+                self.show_reloaded(&t, new);
+                self.save_tasks(&t.borrow());
+            }
+        }
+    }
+
+    /// Shows `new` in place of the current tasks, keeping the same tasks selected if they are still there
+    fn show_reloaded(&self, t : &Rc<RefCell<TasksManager>>, new : TasksManager) {
+        let selected : Vec<TaskData> = {
+            let t = t.borrow();
+            self.get_selected_indices().iter()
+                .filter_map(|index| t.tasks.get(*index).map(TaskData::from_task))
+                .collect()
+        };
+        {
+            let mut t = t.borrow_mut();
+            *t = new;
+            // The indices in the history don't match the new list
+            self.history.borrow_mut().clear();
+            self.update_history_buttons();
+            self.update_tasks_list(&t);
+        }
+        self.disable_task();
+
+        // Exactly the same task, or failing that the one with the same name in the same place
+        let indices : Vec<usize> = {
+            let t = t.borrow();
+            selected.iter()
+                .filter_map(|selected| t.tasks.iter().position(|task| TaskData::from_task(task).same_as(selected))
+                    .or_else(|| t.tasks.iter().position(|task| task.name == selected.name
+                        && task.category == selected.category
+                        && task.sub_category == selected.sub_category)))
+                .collect()
+        };
+        self.select_tasks(&indices);
+    }
+
+    /// Opens the task file at `path`, telling the user if that didn't work
+    fn open_file(&self, tasks : &Weak<RefCell<TasksManager>>, path : String) {
+        match storage::load(&path) {
+            Ok(new) => self.replace_tasks(tasks, new, path),
+            Err(e) => show_error(&self.main_window, "Opening Failed", &format!("Couldn't open:\n\t{}\n{}", path, e)),
+        }
+    }
+
+    /// Writes the tasks to the task file, a failure shows up in the infobar
+    /// and keeps the tasks dirty until some save goes through
+    fn save_tasks(&self, tasks : &TasksManager) {
+        let policy = {
+            let settings = self.settings.borrow();
+            storage::BackupPolicy { keep : settings.backup_count as usize, max_age_days : settings.backup_max_age_days }
+        };
+        let result = {
+            let file = self.file.borrow();
+            match &file.path {
+                Some(path) => storage::save(tasks, path, &policy),
+                None => Err("the task file couldn't be loaded, so it is left untouched(use File > Save As)".to_string()),
+            }
+        };
+        match result {
+            Ok(()) => {
+                self.file.borrow_mut().synced(tasks);
+                match self.mirror_todo_txt(tasks) {
+                    Ok(()) => self.save_infobar.hide(),
+                    Err(e) => {
+                        self.save_error.set_text(&format!("Saved, but the todo.txt mirror couldn't be written: {}", e));
+                        self.save_infobar.show();
+                    }
+                }
+            }
+            Err(e) => {
+                self.file.borrow_mut().dirty = true;
+                self.save_error.set_text(&format!("Your changes are not saved: {}", e));
+                self.save_infobar.show();
+            }
+        }
+        self.update_title();
+    }
+
+    /// Writes the tasks to the todo.txt file they are mirrored to, if there is one
+    fn mirror_todo_txt(&self, tasks : &TasksManager) -> std::io::Result<()> {
+        let path = self.settings.borrow().todo_txt_mirror.clone();
+        match path {
+            Some(path) => {
+                let tasks : Vec<TaskData> = tasks.tasks.iter().map(TaskData::from_task).collect();
+                std::fs::write(path, todo_txt::write(&tasks))?;
+                *self.mirrored.borrow_mut() = Some(tasks);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Starts watching the todo.txt mirror for changes made by todo.txt tools, instead of whatever was watched before
+    fn watch_mirror(&self, tasks : &Weak<RefCell<TasksManager>>) {
+        if let Some(old) = self.mirror_monitor.borrow_mut().take() {
+            old.cancel();
+        }
+        let path = match self.settings.borrow().todo_txt_mirror.clone() {
+            Some(path) => path,
+            None => return,
+        };
+        let monitor = match gio::File::new_for_path(&path).monitor_file(gio::FileMonitorFlags::NONE, None::<&gio::Cancellable>) {
+            Ok(monitor) => monitor,
+            Err(_) => return,
+        };
+
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        monitor.connect_changed(move |_, _, _, event| {
+            match event {
+                gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created => clone.sync_mirror(&tclone),
+                _ => (),
+            }
+        });
+        *self.mirror_monitor.borrow_mut() = Some(monitor);
+    }
+
+    /// Brings the changes made to the todo.txt mirror into the tasks, as a single undo step
+    fn sync_mirror(&self, tasks : &Weak<RefCell<TasksManager>>) {
+        let path = match self.settings.borrow().todo_txt_mirror.clone() {
+            Some(path) => path,
+            None => return,
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => return,
+        };
+        let mirrored = self.mirrored.borrow().clone();
+        // Our own write
+        if mirrored.as_ref().map(|mirrored| text == todo_txt::write(mirrored)).unwrap_or(false) {
+            return;
+        }
+        // Whatever the grid has goes in first, the lines are about the tasks as they are saved.
+        // Keeping on editing leaves the mirror to be written over by the next save
+        if !self.settle_form(tasks) {
+            return;
+        }
+        let t = match tasks.upgrade() {
+            Some(t) => t,
+            None => return,
+        };
+        let existing : Vec<TaskData> = t.borrow().tasks.iter().map(TaskData::from_task).collect();
+        let in_sync = mirrored.map(|mirrored| {
+            existing.len() == mirrored.len() && existing.iter().zip(mirrored.iter()).all(|(a, b)| a.same_as(b))
+        });
+        let import = if in_sync == Some(true) {
+            todo_txt::changes(&existing, &text)
+        }
+        else {
+            // Nothing written since it started or the tasks moved on(a save that failed),
+            // so the lines can't be told apart by where they are
+            merge::import(&existing, todo_txt::parse(&text).into_iter().map(|task| (None, task)).collect())
+        };
+        if !import.changed.is_empty() || !import.removed.is_empty() || !import.added.is_empty() {
+            self.import_tasks(tasks, import);
+        }
+    }
+
+    /// Saves the task grid into its task, or just the tasks if the grid has nothing new
+    fn save_all(&self, tasks : &Weak<RefCell<TasksManager>>) {
+        if self.form_dirty() && self.apply_form(tasks) {
+            return;
+        }
+        if let Some(t) = tasks.upgrade() {
+            self.save_tasks(&t.borrow());
+        }
+    }
+
+    /// What the task grid says, only the touched fields mean anything when bulk editing
+    fn read_form(&self) -> TaskData {
+        let due = if self.due.get_active() {
+            let (year, month, day) = self.date.get_date();
+            let month = Date::month_from_int(month as i32 + 1);
+            let date = LocalDate::ymd(year as i64, month, day as i8).expect("Couldn't parse date...");
+            Some(Date::from(date))
+        }
+        else {
+            None
+        };
+
+        TaskData {
+            name : self.name.get_text().into(),
+            category : self.category.get_text().into(),
+            sub_category : self.sub_cat.get_text().into(),
+            due,
+            done : self.done.get_active(),
+            priority : self.prio.get_value() as u8,
+        }
+    }
+
+    /// Writes the edited fields of the task grid into the tasks it shows and saves,
+    /// `false` if it doesn't show any
+    fn apply_form(&self, tasks : &Weak<RefCell<TasksManager>>) -> bool {
+        let indices = self.form_tasks.borrow().clone();
+        if indices.is_empty() {
+            return false;
+        }
+        let t = match tasks.upgrade() {
+            Some(t) => t,
+            None => return false,
+        };
+        let mut t = t.borrow_mut();
+        if indices.iter().any(|index| *index >= t.tasks.len()) {
+            return false;
+        }
+
+        let touched = self.form_touched.get();
+        let form = self.read_form();
+        let mut edits = Vec::new();
+        let mut rows = Vec::new();
+        for index in indices.iter().copied() {
+            let task = &mut t.tasks[index];
+            let before = TaskData::from_task(task);
+            let mut after = before.clone();
+            touched.copy(&form, &mut after);
+            after.apply(task);
+            rows.push((index, task_row_values(index, task)));
+
+            if !after.same_as(&before) {
+                edits.push(Edit::Change { index, before, after });
+            }
+        }
+        // All of them in one step
+        if let Some(edit) = Edit::batch(edits) {
+            self.push_edit(edit);
+        }
+
+        // Before touching the rows, which might move them and select them again
+        self.form_touched.set(Touched::default());
+        self.save_tasks(&t);
+        drop(t);
+        for (index, values) in rows.iter() {
+            if let Some(iter) = find_task_row(&self.tasks_store, *index) {
+                set_task_row(&self.tasks_store, &iter, values);
+            }
+        }
+        // The changes might not pass the filter anymore
+        self.refilter();
+        if indices.len() > 1 {
+            self.select_tasks(&indices);
+        }
+        true
+    }
+
+    /// Deals with unsaved edits in the task grid before it shows something else,
+    /// `false` if the user would rather keep editing
+    fn settle_form(&self, tasks : &Weak<RefCell<TasksManager>>) -> bool {
+        if !self.form_dirty() {
+            return true;
+        }
+        if self.settings.borrow().autosave != Autosave::Off {
+            self.apply_form(tasks);
+            return true;
+        }
+        match ask_save_changes(&self.main_window, "The task has changes which aren't saved.", "Discard") {
+            gtk::ResponseType::Accept => {
+                self.apply_form(tasks);
+                true
+            }
+            gtk::ResponseType::Reject => {
+                self.form_touched.set(Touched::default());
+                self.update_title();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Deals with everything unsaved, the task grid and tasks a save failed on, before the tasks go away.
+    /// `false` if the user would rather keep them, `discard` is the label of the button which drops them
+    fn settle_file(&self, tasks : &Weak<RefCell<TasksManager>>, discard : &str) -> bool {
+        if self.form_dirty() && self.settings.borrow().autosave != Autosave::Off {
+            self.apply_form(tasks);
+        }
+        if !self.is_dirty() {
+            return true;
+        }
+        match ask_save_changes(&self.main_window, "There are changes which aren't saved.", discard) {
+            gtk::ResponseType::Accept => {
+                self.save_all(tasks);
+                // A failed save keeps them, the infobar says why
+                !self.is_dirty()
+            }
+            gtk::ResponseType::Reject => true,
+            _ => false,
+        }
+    }
+
+    /// Called whenever a field of the task grid changes, `touch` marks which one
+    fn form_edited(&self, tasks : &Weak<RefCell<TasksManager>>, touch : Touch) {
+        if self.filling_form.get() || self.form_tasks.borrow().is_empty() {
+            return;
+        }
+        let mut touched = self.form_touched.get();
+        touch(&mut touched);
+        self.form_touched.set(touched);
+        self.update_title();
+
+        if self.settings.borrow().autosave == Autosave::FieldChange {
+            // Wait for the typing to stop, saving every key would move the task around while its category is typed
+            if let Some(id) = self.autosave_pending.borrow_mut().take() {
+                glib::source_remove(id);
+            }
+            let clone = self.clone();
+            let tclone = tasks.clone();
+            let id = glib::timeout_add_local(AUTOSAVE_DELAY, move || {
+                clone.autosave_pending.borrow_mut().take();
+                if clone.form_dirty() {
+                    clone.apply_form(&tclone);
+                }
+                glib::Continue(false)
+            });
+            *self.autosave_pending.borrow_mut() = Some(id);
+        }
+    }
+
+    /// Empties the search bar and turns every filter off
+    fn clear_filter(&self) {
+        self.search.set_text("");
+        for toggle in [&self.filter_hide_done, &self.filter_overdue, &self.filter_week].iter() {
+            toggle.set_active(false);
+        }
+        self.filter_prio_on.set_active(false);
+        // The search bar only applies the filter after a delay
+        self.apply_filter();
+    }
+
+    /// Reads the search bar into `task_filter` and filters the list again
+    fn apply_filter(&self) {
+        {
+            let mut task_filter = self.task_filter.borrow_mut();
+            task_filter.text = self.search.get_text().to_lowercase();
+            task_filter.hide_done = self.filter_hide_done.get_active();
+            task_filter.overdue_only = self.filter_overdue.get_active();
+            task_filter.due_this_week = self.filter_week.get_active();
+            task_filter.min_priority = if self.filter_prio_on.get_active() {
+                Some(self.filter_prio.get_value() as u32)
+            }
+            else {
+                None
+            };
+        }
+        self.refilter();
+    }
+
+    /// Group rows depend on their children, which `TreeModelFilter` doesn't track on its own,
+    /// so this is needed whenever the filter or the tree's structure changes
+    fn refilter(&self) {
+        // Refiltering drops the selection, but store iters survive it
+        let (paths, model) = self.tasks_list.get_selection().get_selected_rows();
+        let selected : Vec<TreeIter> = paths.iter()
+            .filter_map(|path| model.get_iter(path))
+            .map(|iter| self.to_store_iter(&iter))
+            .collect();
+
+        self.filter_model.refilter();
+        if !self.task_filter.borrow().is_empty() {
+            // Show every match
+            self.tasks_list.expand_all();
+        }
+
+        for iter in selected.iter() {
+            self.select_row(iter);
+        }
+        self.update_views();
+    }
+
+    /// Shows the tasks the filter lets through in the calendar or the board, whichever is being looked at
+    fn update_views(&self) {
+        match self.views.get_visible_child_name().as_deref() {
+            Some("calendar") => self.calendar.show(self.filtered_tasks(|model, iter, index| {
+                let due = model.get_value(iter, COL_DUE as i32).get_some().unwrap_or(dates::NO_DUE);
+                if due == dates::NO_DUE {
+                    return None;
+                }
+                Some(CalendarTask {
+                    index,
+                    name : get_row_text(model, iter, COL_NAME),
+                    due,
+                    priority : model.get_value(iter, COL_PRIO as i32).get_some().unwrap_or(0),
+                    done : model.get_value(iter, COL_DONE as i32).get_some().unwrap_or(false),
+                })
+            })),
+            Some("board") => self.board.show(self.filtered_tasks(|model, iter, index| {
+                Some(BoardTask {
+                    index,
+                    name : get_row_text(model, iter, COL_NAME),
+                    category : get_row_text(model, iter, COL_CAT),
+                    sub_category : get_row_text(model, iter, COL_SUB),
+                    due : get_row_text(model, iter, COL_DUE_TEXT),
+                    priority : model.get_value(iter, COL_PRIO as i32).get_some().unwrap_or(0),
+                    done : model.get_value(iter, COL_DONE as i32).get_some().unwrap_or(false),
+                })
+            })),
+            _ => (),
+        }
+    }
+
+    /// Runs `task` over every task row the filter lets through(with the task's index), collecting what it returns
+    /// in the order the tree is sorted in
+    fn filtered_tasks<T>(&self, task : impl Fn(&TreeModel, &TreeIter, usize) -> Option<T>) -> Vec<T> {
+        let mut tasks = Vec::new();
+        self.sort_model.foreach(|model, _, iter| {
+            if let Some(shown) = get_task_index(model, iter).and_then(|index| task(model, iter, index)) {
+                tasks.push(shown);
+            }
+            false
+        });
+        tasks
+    }
+
+    /// Fills the tree with `tasks` again, whatever the task grid showed is dropped(settle it first)
+    fn update_tasks_list(&self, tasks : &TasksManager) {
+        self.disable_task();
+        // Refill the same store so the signals and sort order connected to it stay intact
+        let model = &self.tasks_store;
+        model.clear();
+
+        let grouping = self.settings.borrow().grouping;
+        match grouping {
+            Grouping::Category => {
+                let categories = tasks.get_categories();
+
+                for cat in categories.iter() {
+                    for (i,t) in tasks.tasks.iter().enumerate().filter(|(_,t)| t.category == cat.as_str()) {
+                        let parent = get_or_insert_group(model, &self.task_groups(t));
+                        let _ = insert_task_row(model, Some(&parent), i, t);
+                    }
+                }
+            }
+            Grouping::Agenda => {
+                let today = dates::today();
+
+                for bucket in Bucket::ALL.iter() {
+                    for (i,t) in tasks.tasks.iter().enumerate().filter(|(_,t)| task_bucket(t, today) == *bucket) {
+                        let parent = get_or_insert_group(model, &self.task_groups(t));
+                        let _ = insert_task_row(model, Some(&parent), i, t);
+                    }
+                }
+            }
+        }
+        self.refilter();
     }
 
     fn disable_task(&self) {
         self.task_grid.set_sensitive(false);
+        self.form_tasks.borrow_mut().clear();
+        self.form_touched.set(Touched::default());
+        self.update_title();
     }
 
-    fn update_task(&self, task : &Task) {
+    /// Shows the tasks at `indices` in the task grid, fields they don't agree on show as mixed
+    fn update_task(&self, indices : Vec<usize>, tasks : &TasksManager) {
+        let shown : Vec<&Task> = indices.iter().filter_map(|index| tasks.tasks.get(*index)).collect();
+        if shown.is_empty() || shown.len() != indices.len() {
+            self.disable_task();
+            return;
+        }
+        let common = Common::of(&shown);
+        let bulk = shown.len() > 1;
+
         self.task_grid.set_sensitive(true);
+        self.filling_form.set(true);
+
+        // Giving a bunch of tasks the same name makes no sense
+        self.name.set_sensitive(!bulk);
+        let count = format!("{} tasks", shown.len());
+        self.name.set_placeholder_text(if bulk { Some(count.as_str()) } else { None });
+        let mixed = if bulk { Some("mixed") } else { None };
+        self.category.set_placeholder_text(mixed);
+        self.sub_cat.set_placeholder_text(mixed);
+
+        self.name.set_text(common.name.as_deref().unwrap_or(""));
+        self.category.set_text(common.category.as_deref().unwrap_or(""));
+        self.sub_cat.set_text(common.sub_category.as_deref().unwrap_or(""));
+
+        match common.due {
+            Some(due) => {
+                self.due_label.set_text("Due");
+                self.due.set_active(due.is_some());
+                if let Some(date) = due {
+                    let date = date.to_localdate().expect("error converting date");
+
+                    self.date.select_month(date.month() as u32 -1, date.year() as u32);
+                    self.date.select_day(date.day() as u32);
+                }
+            }
+            None => {
+                self.due_label.set_text("Due (mixed)");
+                self.due.set_active(false);
+            }
+        }
 
-        self.name.set_text(&task.name);
-        self.category.set_text(&task.category);
-        self.sub_cat.set_text(&task.sub_category);
-        self.due.set_active(task.due.is_some());
+        // Setting it active clears the inconsistent look, so that goes last
+        self.done.set_active(common.done.unwrap_or(false));
+        self.done.set_inconsistent(common.done.is_none());
 
-        if let Some(date) = task.due {
-            let date = date.to_localdate().expect("error converting date");
-            
-            self.date.select_month(date.month() as u32 -1, date.year() as u32);
-            self.date.select_day(date.day() as u32);
+        match common.priority {
+            Some(prio) => {
+                self.prio_label.set_text("Priority");
+                self.prio.set_value(prio as f64);
+            }
+            None => self.prio_label.set_text("Priority (mixed)"),
         }
 
-        self.done.set_active(task.done);
-        self.prio.set_value(task.priority as f64);
+        self.filling_form.set(false);
+        *self.form_tasks.borrow_mut() = indices;
+        self.form_touched.set(Touched::default());
+        self.update_title();
     }
 
     fn connect_ui(self, tasks : Weak<RefCell<TasksManager>>) {
@@ -109,35 +1813,114 @@ impl UILayout {
         let clone = self.clone();
         let tclone = tasks.clone();
         self.tasks_list.get_selection()
-            .connect_changed(move |ts| {
-                let task = ts.get_selected();
+            .connect_changed(move |_| {
+                let selected = clone.get_selected_indices();
+                let shown = clone.form_tasks.borrow().clone();
 
-                match task {
-                    Some((model,iter)) => {
-                        let (tf, cat) = get_selected_data(&model, &iter);
-                        
-                        if tf.is_some() && cat.is_some() {
-                            let tasks = tclone.upgrade();
-                            match tasks {
-                                Some(t) => {
-                                    let t = t.borrow();
-                                    let task = find_task_in_list(&t.tasks, &cat.unwrap(),&tf.unwrap());
-                                    if let Some(t) = task {
-                                        clone.update_task(&t);
-                                    }
-                                    else {
-                                        clone.disable_task();
-                                    }
-                                },
-                                None => ()
-                            }
-                        }                
-                    },
-                    None => {
-                        clone.disable_task();
+                // Don't let unsaved edits go without a word
+                if clone.form_dirty() {
+                    // Still the tasks being edited(or back to them)
+                    if selected == shown {
+                        return;
+                    }
+                    if !clone.settle_form(&tclone) {
+                        clone.select_tasks(&shown);
+                        return;
                     }
+                    // Saving might have moved the edited tasks and selected them again
+                    if clone.get_selected_indices() != selected {
+                        clone.select_tasks(&selected);
+                        return;
+                    }
+                }
+
+                if selected.is_empty() {
+                    clone.disable_task();
+                    return;
+                }
+                if let Some(t) = tclone.upgrade() {
+                    clone.update_task(selected, &t.borrow());
                 }
         });
+
+        // Connect the quick add entry
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.quick_add.connect_activate(move |entry| {
+            let text = entry.get_text().to_string();
+            match quick_add::parse(&text, dates::today()) {
+                Ok(quick) => {
+                    // The new task gets selected, so the task grid has to let go first
+                    if !clone.settle_form(&tclone) {
+                        return;
+                    }
+                    if let Some(index) = clone.add_task(&tclone, quick_task(quick)) {
+                        entry.set_text("");
+                        clone.select_task(index);
+                    }
+                }
+                Err(e) => clone.show_quick_add_error(&text, &e),
+            }
+        });
+        // Whatever was wrong is being fixed
+        self.quick_add.connect_changed(|entry| {
+            entry.get_style_context().remove_class("error");
+            entry.set_icon_from_icon_name(gtk::EntryIconPosition::Secondary, None);
+        });
+
+        // Connect the task grid fields, any change makes it dirty
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        let edited = Rc::new(move |touch : Touch| clone.form_edited(&tclone, touch));
+        let entries : [(&Entry, Touch); 3] = [
+            (&self.name, |t| t.name = true),
+            (&self.category, |t| t.category = true),
+            (&self.sub_cat, |t| t.sub_category = true),
+        ];
+        for (entry, touch) in entries.iter() {
+            let edited = Rc::clone(&edited);
+            let touch = *touch;
+            entry.connect_changed(move |_| edited(touch));
+        }
+        let e = Rc::clone(&edited);
+        self.due.connect_changed_active(move |_| e(|t| t.due = true));
+        let e = Rc::clone(&edited);
+        self.date.connect_day_selected(move |_| e(|t| t.due = true));
+        let e = Rc::clone(&edited);
+        self.done.connect_toggled(move |done| {
+            // No longer mixed once clicked
+            done.set_inconsistent(false);
+            e(|t| t.done = true)
+        });
+        let e = Rc::clone(&edited);
+        self.prio.connect_value_changed(move |_| e(|t| t.priority = true));
+
+        // Connect search bar
+        let clone = self.clone();
+        self.search.connect_search_changed(move |_| clone.apply_filter());
+        for toggle in [&self.filter_hide_done, &self.filter_overdue, &self.filter_week].iter() {
+            let clone = self.clone();
+            toggle.connect_toggled(move |_| clone.apply_filter());
+        }
+        let clone = self.clone();
+        self.filter_prio_on.connect_toggled(move |_| clone.apply_filter());
+        let clone = self.clone();
+        self.filter_prio.connect_value_changed(move |_| clone.apply_filter());
+
+        // Connect the grouping combo box, starting from whatever was picked last time
+        let grouping = self.settings.borrow().grouping;
+        self.grouping.set_active_id(Some(grouping.as_str()));
+        if grouping == Grouping::Agenda {
+            self.hide_done_in_agenda();
+        }
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.grouping.connect_changed(move |combo| {
+            if let Some(grouping) = combo.get_active_id().and_then(|id| Grouping::parse(&id)) {
+                clone.set_grouping(&tclone, grouping);
+            }
+        });
+
         // Connect date being disabled
         let date = self.date.clone();
         self.due.connect_changed_active(move |c| {
@@ -147,129 +1930,87 @@ impl UILayout {
         // Connect save
         let clone = self.clone();
         let tclone = tasks.clone();
-        self.save.connect_clicked(move |_| {
-            let selector = clone.tasks_list.get_selection();
-            match selector.get_selected() {
-                Some((model, iter)) => {
-                    let (tf, cat) = get_selected_data(&model,&iter);
-
-                    if tf.is_some() && cat.is_some() {
-                        let tf = tf.unwrap();
-                        let cat = cat.unwrap();
-                        match tclone.upgrade() {
-                            Some(t) => {
-                                let mut t = t.borrow_mut();
-                                let mut task = find_task_in_list_mut(&mut t.tasks, &cat, &tf).expect("couldnt find the selected task");
-                                
-                                task.name = clone.name.get_text().into();
-                                task.category = clone.category.get_text().into();
-                                task.sub_category = clone.sub_cat.get_text().into();
-                                
-                                let due = if clone.due.get_active() {
-                                    let (year, month, day) = clone.date.get_date();
-                                    let month = Date::month_from_int(month as i32 + 1);
-                                    let date = LocalDate::ymd(year as i64, month, day as i8).expect("Couldn't parse date...");
-                                    Some(Date::from(date))
-                                }
-                                else {
-                                    None
-                                };
-
-                                task.due = due;
-                                task.done = clone.done.get_active();
-
-                                task.priority = clone.prio.get_value() as u8;
-                                let tf = task.formatted(true);
-
-                                let _ = t.save(&TasksManager::default_path());
-                                drop(t);
-                                // clone.update_tasks_list(&*t);
-                                // im only using TreeStore here so it should work, but if not i will be sad :(
-                                let model : gtk::TreeStore = unsafe {
-                                    // The model should be a treestore tbh
-                                    model.unsafe_cast()
-                                };
-                                model.set_value(&iter, 0, &tf.to_value());
+        self.add_action("save", move || clone.save_all(&tclone));
+
+        // Connect delete task
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.add_action("delete", move || {
+            let selected = clone.get_selected_indices();
+            if selected.is_empty() {
+                return;
+            }
+            if let Some(t) = tclone.upgrade() {
+                let mut t = t.borrow_mut();
+                let listed : Vec<String> = selected.iter()
+                    .filter_map(|index| t.tasks.get(*index))
+                    .map(|task| format!("\t{}", task.formatted(true)))
+                    .collect();
+                if listed.len() != selected.len() {
+                    return;
+                }
+
+                // A long list won't fit on the screen
+                const MAX_LISTED : usize = 10;
+                let mut tf = listed.iter().take(MAX_LISTED).cloned().collect::<Vec<_>>().join("\n");
+                if listed.len() > MAX_LISTED {
+                    tf.push_str(&format!("\n\t...and {} more", listed.len() - MAX_LISTED));
+                }
+                let message = if selected.len() == 1 {
+                    format!("Are you sure you want to delete:\n{}", tf)
+                }
+                else {
+                    format!("Are you sure you want to delete these {} tasks:\n{}", selected.len(), tf)
+                };
 
+                let dia = gtk::MessageDialog::new(
+                    Some(&clone.main_window),
+                    gtk::DialogFlags::DESTROY_WITH_PARENT,
+                    gtk::MessageType::Warning,
+                    gtk::ButtonsType::YesNo,
+                    &message
+                );
+                dia.set_title("Remove Task");
+                let res = dia.run();
+                dia.hide();
 
-                            }
-                            None => {}
-                        }
+                if res == gtk::ResponseType::Yes {
+                    clone.disable_task();
+
+                    // Back to front so the indices of the ones left to remove stay put
+                    let mut edits = Vec::new();
+                    for index in selected.iter().rev().copied() {
+                        edits.push(Edit::Remove { index, task : TaskData::from_task(&t.tasks[index]) });
+                        t.remove_task(index);
+                    }
+                    if let Some(edit) = Edit::batch(edits) {
+                        clone.push_edit(edit);
                     }
-                },
-                None => ()
-            }
-        });
 
-        // Connect delete task
-        let clone = self.clone();
-        let tclone = tasks.clone();
-        self.delete.connect_clicked(move |_| {
-            let selector = clone.tasks_list.get_selection();
-            match selector.get_selected() {
-                Some((model, iter)) => {
-                    let  (tf, cat) = get_selected_data(&model,&iter);
-
-                    if tf.is_some() && cat.is_some() {
-                        let tf = tf.unwrap();
-                        let cat = cat.unwrap();
-
-                        match tclone.upgrade() {
-                            Some(t) => {
-                                let mut t = t.borrow_mut();
-
-                                let dia = gtk::MessageDialog::new(
-                                    Some(&clone.main_window),
-                                    gtk::DialogFlags::DESTROY_WITH_PARENT,
-                                    gtk::MessageType::Warning,
-                                    gtk::ButtonsType::YesNo,
-                                    &format!("Are you sure you want to delete:\n\t{}", tf)
-                                );
-                                dia.set_title("Remove Task");
-                                let res = dia.run();
-                                dia.hide();
-
-                                match res {
-                                    gtk::ResponseType::Yes => {
-                                        // find the task
-                                        let task_i = t.tasks.iter()
-                                            .enumerate()
-                                            .find(|(_,t)| t.formatted(true) == tf && t.category == cat)
-                                            .map(|(i,_)| i);
-                                        t.remove_task(task_i.unwrap_or(9999));
-
-                                        let model : gtk::TreeStore = unsafe {
-                                            model.unsafe_cast()
-                                        };
-                                        clone.disable_task();
-                                        
-                                        let _ = t.save(&TasksManager::default_path());
-                                        
-                                        // Upon calling `model.remove` the `selection changed` closure will be envoked
-                                        // which borrows the tasks manager, thus we need to drop beforehand 
-                                        drop(t); 
-                                        model.remove(&iter);
-                                    }
-                                    _ => ()
-
-                                }
+                    clone.save_tasks(&t);
 
-                            }
-                            None => ()
+                    // Upon calling `model.remove` the `selection changed` closure will be envoked
+                    // which borrows the tasks manager, thus we need to drop beforehand
+                    drop(t);
+                    let model = &clone.tasks_store;
+                    for index in selected.iter().rev() {
+                        if let Some(iter) = find_task_row(model, *index) {
+                            // Every task after the removed one just moved one slot back
+                            shift_task_indices(model, *index);
+                            model.remove(&iter);
                         }
-                    
                     }
-
                 }
-                None => ()
             }
-
         });
 
         // Connect remove done
         let clone = self.clone();
         let tclone = tasks.clone();
-        self.remove_done.connect_clicked(move |_| {
+        self.add_action("remove-done", move || {
+            if !clone.settle_form(&tclone) {
+                return;
+            }
             let dia = gtk::MessageDialog::new(
                 Some(&clone.main_window),
                 gtk::DialogFlags::DESTROY_WITH_PARENT,
@@ -285,10 +2026,18 @@ impl UILayout {
                 match tclone.upgrade() {
                     Some(t) => {
                         let mut t = t.borrow_mut();
+                        let removed : Vec<(usize, TaskData)> = t.tasks.iter()
+                            .enumerate()
+                            .filter(|(_,t)| t.done)
+                            .map(|(i,t)| (i, TaskData::from_task(t)))
+                            .collect();
                         t.remove_done();
+                        if !removed.is_empty() {
+                            clone.push_edit(Edit::RemoveMany { tasks : removed });
+                        }
 
                         clone.update_tasks_list(&*t);
-                        let _ = t.save(&TasksManager::default_path());
+                        clone.save_tasks(&t);
                     }
                     None => ()
                 }
@@ -298,165 +2047,585 @@ impl UILayout {
         // Connect new task button
         let clone = self.clone();
         let tclone = tasks.clone();
-        self.new.connect_clicked(move |_| {
-            match tclone.upgrade() {
-                Some(t) => {
-                    let mut t = t.borrow_mut();
-                    // Make sure we dont already have a 'new task'
-                    let n;
-                    if !t.tasks.iter().any(|t| t.name == "new task") {
-                        let new = Task::new("new task");
-    
-                        t.add_task(new);
-                        clone.update_tasks_list(&*t);
-                        n = t.tasks.len() -1;
+        self.add_action("new", move || {
+            if !clone.settle_form(&tclone) {
+                return;
+            }
+            let mut task = Task::new("new task");
+            // Goes next to whatever is selected, otherwise in the default category if there is one
+            if let Some((category, sub_category)) = clone.selected_placement() {
+                task.category = category;
+                task.sub_category = sub_category;
+            }
+            else if let Some(category) = clone.settings.borrow().default_category.clone() {
+                task.category = category;
+                task.sub_category = String::new();
+            }
+            if let Some(index) = clone.add_task(&tclone, task) {
+                // The filter might hide it, and the grid would be editing a task nobody can see
+                let hidden = find_task_row(&clone.tasks_store, index).map(|iter| clone.to_view_iter(&iter).is_none());
+                if hidden == Some(true) {
+                    clone.clear_filter();
+                }
+                clone.select_task(index);
+                // Ready to type the actual name over it
+                clone.name.grab_focus();
+                clone.name.select_region(0, -1);
+            }
+        });
+
+        // Connect the save error infobar
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.save_infobar.connect_response(move |bar, res| {
+            match res {
+                // Retry
+                gtk::ResponseType::Other(1) => {
+                    if let Some(t) = tclone.upgrade() {
+                        clone.save_tasks(&t.borrow());
                     }
-                    else {
-                        n = {
-                            let mut res = 0;
-                            for (i,t) in t.tasks.iter().enumerate() {
-                                if t.name == "new task" {
-                                    res = i;
-                                    break;
-                                }
-                            }
-                            res
-                        };
+                }
+                // Closing only hides the message, the tasks stay dirty
+                _ => bar.hide(),
+            }
+        });
+
+        // Connect the file menu
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_open.connect_activate(move |_| {
+            if !clone.settle_file(&tclone, "Discard") {
+                return;
+            }
+            if let Some(path) = choose_file(Some(&clone.main_window), "Open Task File", gtk::FileChooserAction::Open) {
+                clone.open_file(&tclone, path);
+            }
+        });
+
+        let filter = gtk::RecentFilter::new();
+        filter.add_application(APP_NAME);
+        self.recent_menu.set_filter(Some(&filter));
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.recent_menu.connect_item_activated(move |menu| {
+            if !clone.settle_file(&tclone, "Discard") {
+                return;
+            }
+            let path = menu.get_current_uri()
+                .and_then(|uri| glib::filename_from_uri(&uri).ok())
+                .map(|(path, _)| path.to_string_lossy().into_owned());
+            if let Some(path) = path {
+                clone.open_file(&tclone, path);
+            }
+        });
+
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_new_list.connect_activate(move |_| {
+            if !clone.settle_file(&tclone, "Discard") {
+                return;
+            }
+            if let Some(path) = choose_file(Some(&clone.main_window), "New Task List", gtk::FileChooserAction::Save) {
+                clone.replace_tasks(&tclone, storage::empty_tasks(), path);
+                // Creates the file right away
+                if let Some(t) = tclone.upgrade() {
+                    clone.save_tasks(&t.borrow());
+                }
+            }
+        });
+
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_save_as.connect_activate(move |_| {
+            if let Some(path) = choose_file(Some(&clone.main_window), "Save Task List As", gtk::FileChooserAction::Save) {
+                let old = clone.file.borrow_mut().path.replace(path.clone());
+                clone.save_all(&tclone);
+                // Until the new file is written the old one is still where the tasks are
+                if clone.file.borrow().dirty {
+                    clone.file.borrow_mut().path = old;
+                    clone.update_title();
+                    return;
+                }
+                add_to_recent(&path);
+                clone.watch_file(&tclone);
+            }
+        });
+
+        // Connect CSV import/export
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_import_csv.connect_activate(move |_| {
+            if !clone.settle_form(&tclone) {
+                return;
+            }
+            let path = match choose_file(Some(&clone.main_window), "Import CSV", gtk::FileChooserAction::Open) {
+                Some(path) => path,
+                None => return,
+            };
+            let text = match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) => return show_error(&clone.main_window, "Import CSV", &format!("Couldn't read {}: {}", path, e)),
+            };
+            let rows = csv::parse(&text);
+            if rows.is_empty() {
+                return show_error(&clone.main_window, "Import CSV", &format!("{} has no rows", path));
+            }
+            let existing = match tclone.upgrade() {
+                Some(t) => t.borrow().tasks.iter().map(TaskData::from_task).collect(),
+                None => return,
+            };
+            if let Some(new_tasks) = csv::ask_import(&clone.main_window, rows, existing) {
+                clone.import_tasks(&tclone, merge::Import { added : new_tasks, ..Default::default() });
+            }
+        });
+
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_export_csv.connect_activate(move |_| {
+            let path = match choose_file(Some(&clone.main_window), "Export CSV", gtk::FileChooserAction::Save) {
+                Some(path) => path,
+                None => return,
+            };
+            let t = match tclone.upgrade() {
+                Some(t) => t,
+                None => return,
+            };
+            // Only what the filter lets through, in the order of the tree
+            let indices = clone.filtered_tasks(|_, _, index| Some(index));
+            let t = t.borrow();
+            let shown : Vec<&Task> = indices.iter().filter_map(|i| t.tasks.get(*i)).collect();
+            if let Err(e) = std::fs::write(&path, csv::write(&shown)) {
+                show_error(&clone.main_window, "Export CSV", &format!("Couldn't write {}: {}", path, e));
+            }
+        });
+
+        // Connect iCalendar import/export
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_import_ical.connect_activate(move |_| {
+            if !clone.settle_form(&tclone) {
+                return;
+            }
+            let path = match choose_file(Some(&clone.main_window), "Import iCalendar", gtk::FileChooserAction::Open) {
+                Some(path) => path,
+                None => return,
+            };
+            let text = match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) => return show_error(&clone.main_window, "Import iCalendar", &format!("Couldn't read {}: {}", path, e)),
+            };
+            let todos = ical::parse(&text);
+            if todos.is_empty() {
+                return show_error(&clone.main_window, "Import iCalendar", &format!("{} has no tasks", path));
+            }
+            let existing : Vec<TaskData> = match tclone.upgrade() {
+                Some(t) => t.borrow().tasks.iter().map(TaskData::from_task).collect(),
+                None => return,
+            };
+            clone.import_tasks(&tclone, ical::merge(&existing, todos));
+        });
+
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_export_ical.connect_activate(move |_| {
+            let path = match choose_file(Some(&clone.main_window), "Export iCalendar", gtk::FileChooserAction::Save) {
+                Some(path) => path,
+                None => return,
+            };
+            let tasks : Vec<TaskData> = match tclone.upgrade() {
+                Some(t) => t.borrow().tasks.iter().map(TaskData::from_task).collect(),
+                None => return,
+            };
+            let stamp = glib::DateTime::new_now_utc().format("%Y%m%dT%H%M%SZ").map(|s| s.to_string()).unwrap_or_default();
+            if let Err(e) = std::fs::write(&path, ical::write(&tasks, &stamp)) {
+                show_error(&clone.main_window, "Export iCalendar", &format!("Couldn't write {}: {}", path, e));
+            }
+        });
+
+        // Connect todo.txt import/export and mirroring
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_import_todo_txt.connect_activate(move |_| {
+            if !clone.settle_form(&tclone) {
+                return;
+            }
+            let path = match choose_file(Some(&clone.main_window), "Import todo.txt", gtk::FileChooserAction::Open) {
+                Some(path) => path,
+                None => return,
+            };
+            let text = match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) => return show_error(&clone.main_window, "Import todo.txt", &format!("Couldn't read {}: {}", path, e)),
+            };
+            let existing : Vec<TaskData> = match tclone.upgrade() {
+                Some(t) => t.borrow().tasks.iter().map(TaskData::from_task).collect(),
+                None => return,
+            };
+            // Tasks we already have get updated rather than added again
+            let import = merge::import(&existing, todo_txt::parse(&text).into_iter().map(|task| (None, task)).collect());
+            clone.import_tasks(&tclone, import);
+        });
+
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_export_todo_txt.connect_activate(move |_| {
+            let path = match choose_file(Some(&clone.main_window), "Export todo.txt", gtk::FileChooserAction::Save) {
+                Some(path) => path,
+                None => return,
+            };
+            let tasks : Vec<TaskData> = match tclone.upgrade() {
+                Some(t) => t.borrow().tasks.iter().map(TaskData::from_task).collect(),
+                None => return,
+            };
+            if let Err(e) = std::fs::write(&path, todo_txt::write(&tasks)) {
+                show_error(&clone.main_window, "Export todo.txt", &format!("Couldn't write {}: {}", path, e));
+            }
+        });
+
+        self.menu_mirror_todo_txt.set_active(self.settings.borrow().todo_txt_mirror.is_some());
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_mirror_todo_txt.connect_toggled(move |item| {
+            let path = if item.get_active() {
+                match choose_file(Some(&clone.main_window), "Mirror to todo.txt", gtk::FileChooserAction::Save) {
+                    Some(path) => Some(path),
+                    None => {
+                        // Toggles it again, which clears the setting
+                        item.set_active(false);
+                        return;
                     }
-                    // Select the new task
-                    let model = clone.tasks_list.get_model().expect("couldnt get model?!?");
-                    let iter = model.iter_nth_child(None, n as i32);
-
-                    // Why?
-                    //      Forcefully dorp 't' early because we borrow it here as mut
-                    //      while we want to select(which will enforce another borrow_mut)
-                    // Anyway, dropping it early means we are no longer borrow_mut-ing it!
-                    drop(t);
+                }
+            }
+            else {
+                None
+            };
+            let mirrored = path.is_some();
+            {
+                let mut settings = clone.settings.borrow_mut();
+                settings.todo_txt_mirror = path;
+                let _ = settings.save();
+            }
+            *clone.mirrored.borrow_mut() = None;
+            // The mirror starts out up to date
+            if let (true, Some(t)) = (mirrored, tclone.upgrade()) {
+                clone.save_tasks(&t.borrow());
+            }
+            clone.watch_mirror(&tclone);
+        });
 
-                    match iter {
-                        Some(t) => {
-                            let selector = clone.tasks_list.get_selection();
-                            selector.select_iter(&t);
-                        }
-                        None => {}
+        // Connect the report export
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_export_report.connect_activate(move |_| {
+            let choice = match report::ask(&clone.main_window) {
+                Some(choice) => choice,
+                None => return,
+            };
+            let t = match tclone.upgrade() {
+                Some(t) => t,
+                None => return,
+            };
+            let indices = if choice.filtered {
+                clone.filtered_tasks(|_, _, index| Some(index))
+            }
+            else {
+                (0..t.borrow().tasks.len()).collect()
+            };
+            let shown : Vec<TaskData> = indices.iter().filter_map(|i| t.borrow().tasks.get(*i).map(TaskData::from_task)).collect();
+            let today = dates::today();
+            let title = format!(
+                "Tasks in {}, {}",
+                clone.file.borrow().display_name(),
+                dates::format_due(&dates::date_from_days(today)),
+            );
+            let text = report::render(choice.format, &title, &shown, today);
+
+            match choice.output {
+                report::Output::Clipboard => gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD).set_text(&text),
+                report::Output::File => {
+                    // Named in the chooser, so it can ask before overwriting anything
+                    let name = format!("report.{}", choice.format.extension());
+                    let path = match choose_file_named(Some(&clone.main_window), "Export Report", gtk::FileChooserAction::Save, Some(&name)) {
+                        Some(path) => path,
+                        None => return,
+                    };
+                    if let Err(e) = std::fs::write(&path, text) {
+                        show_error(&clone.main_window, "Export Report", &format!("Couldn't write {}: {}", path, e));
                     }
+                }
+            }
+        });
+
+        // Connect restoring from a backup
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.menu_restore_backup.connect_activate(move |_| {
+            if !clone.settle_form(&tclone) {
+                return;
+            }
+            let path = clone.file.borrow().path.clone();
+            let backups = path.map(|path| storage::backups(&path)).unwrap_or_default();
+            if backups.is_empty() {
+                return show_error(&clone.main_window, "Restore from Backup", "There are no backups of this task file yet");
+            }
+            if let Some(restore) = restore::ask_restore(&clone.main_window, &backups) {
+                clone.restore_tasks(&tclone, restore);
+            }
+        });
+
+        // Connect undo/redo
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.add_action("undo", move || clone.step_history(&tclone, true));
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.add_action("redo", move || clone.step_history(&tclone, false));
+
+        // Connect the keyboard shortcuts
+        self.connect_shortcuts(&tasks);
+        // The actions start out enabled
+        self.update_history_buttons();
+        self.update_title();
+
+        // Connect the calendar and the board, they only keep up with the tasks while they are shown
+        let clone = self.clone();
+        self.views.connect_property_visible_child_notify(move |_| clone.update_views());
+        let clone = self.clone();
+        self.calendar.connect_task_activated(move |index| clone.select_task(index));
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.calendar.connect_task_dropped(move |index, day| {
+            if clone.settle_form(&tclone) {
+                if let Some(due) = dates::date_from_days(day) {
+                    clone.change_tasks(&tclone, &[index], |task| task.due = Some(due));
+                }
+            }
+        });
+        let settings = Rc::clone(&self.settings);
+        self.board.connect_columns_changed(move |columns| {
+            let mut settings = settings.borrow_mut();
+            settings.board_columns = columns;
+            let _ = settings.save();
+        });
+        let clone = self.clone();
+        self.board.connect_task_activated(move |index| clone.select_task(index));
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.board.connect_task_dropped(move |index, column| {
+            if !clone.settle_form(&tclone) {
+                return;
+            }
+            // Dragging a card out of Done means it isn't done after all
+            clone.change_tasks(&tclone, &[index], |task| match column {
+                Column::Category(category) => {
+                    task.category = category.clone();
+                    task.done = false;
+                }
+                Column::Priority(priority) => {
+                    task.priority = *priority as u8;
+                    task.done = false;
+                }
+                Column::Done => task.done = true,
+            });
+        });
+
+        // Connect dragging tasks around the tree
+        self.enable_tree_dnd(self.settings.borrow().grouping == Grouping::Category);
+
+        // The tree only marks where a drop goes for drags of its model's rows
+        self.tasks_list.connect_drag_motion(|view, _, x, y, _| {
+            match view.get_dest_row_at_pos(x, y) {
+                Some((path, pos)) => view.set_drag_dest_row(path.as_ref(), pos),
+                None => view.set_drag_dest_row(None, gtk::TreeViewDropPosition::Before),
+            }
+            gtk::Inhibit(true)
+        });
 
+        let clone = self.clone();
+        self.tasks_list.connect_drag_data_get(move |_, _, data, _, _| {
+            // Only single tasks get dragged, anything else drops as nothing
+            if let [index] = clone.get_selected_indices()[..] {
+                data.set(&data.get_target(), 8, index.to_string().as_bytes());
+            }
+        });
+
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.tasks_list.connect_drag_data_received(move |view, _, x, y, data, _, _| {
+            let from = match String::from_utf8(data.get_data()).ok().and_then(|s| s.parse::<usize>().ok()) {
+                Some(from) => from,
+                None => return,
+            };
+            let end = match tclone.upgrade() {
+                Some(t) => t.borrow().tasks.len(),
+                None => return,
+            };
 
-                    
+            let target = view.get_dest_row_at_pos(x, y)
+                .and_then(|(path, pos)| Some((clone.sort_model.get_iter(&path?)?, pos)));
+            match target {
+                Some((iter, pos)) => {
+                    let iter = clone.to_store_iter(&iter);
+                    let model : &TreeModel = clone.tasks_store.upcast_ref();
+                    match get_task_index(model, &iter) {
+                        // Next to another task, in its category
+                        Some(index) => {
+                            let (category, sub_category) = get_row_placement(model, &iter);
+                            let at = match pos {
+                                gtk::TreeViewDropPosition::Before | gtk::TreeViewDropPosition::IntoOrBefore => index,
+                                _ => index + 1,
+                            };
+                            clone.move_task(&tclone, from, at, category, sub_category)
+                        }
+                        // Onto a category, at the end of it
+                        None => {
+                            let (category, sub_category) = get_group_placement(model, &iter);
+                            clone.move_task(&tclone, from, end, category, sub_category)
+                        }
+                    };
+                }
+                // Empty space makes a new category, asked for once the drag is over
+                None => {
+                    let clone = clone.clone();
+                    let tclone = tclone.clone();
+                    glib::idle_add_local(move || {
+                        if let Some(category) = ask_text(&clone.main_window, "New Category", "Move the task to a new category named:") {
+                            clone.move_task(&tclone, from, end, category, String::new());
+                        }
+                        glib::Continue(false)
+                    });
                 }
-                None => ()
             }
         });
 
         // Connect row changed(to see if we need to refresh or not)
         let clone = self.clone();
         let tclone = tasks.clone();
-        let model = self.tasks_list.get_model().expect("Couldn't get model wtf");
-        model.connect_row_changed(move |model,_,iter| {
-            let (tf, cat) = get_selected_data(model,iter);
-            if tf.is_some() && cat.is_some() {
-                let tf = tf.unwrap();
-                let cat = cat.unwrap();
+        self.tasks_store.connect_row_changed(move |model,_,iter| {
+            if let Some(index) = get_task_index(model.upcast_ref(), iter) {
+                // Whatever group the task is in, according to the parent rows
+                let placement = get_row_groups(model.upcast_ref(), iter);
 
-                let parent_path = {
-                    let mut path = model.get_path(&iter).expect("couldn't get path");
-                    let _ = path.up();
-                    path
-                };
                 // Do stuff with the task
                 match tclone.upgrade() {
                     Some(manager) => {
                         let manager = manager.borrow();
-                        let task = manager.tasks.iter()
-                            .find(|t| t.formatted(true) == tf);
                         
-                        if task.is_some() {
-                            let task = task.unwrap();
-                            if task.category != cat {
-                                // move the task to a new category
-                                let model : gtk::TreeStore = unsafe {
-                                    model.clone().unsafe_cast()
-                                };
-                                let clean_iter = model.get_iter_first().expect("couldnt get iter");
-                                let mut wanted_parent = None;
-                                loop {
-                                    if !model.iter_next(&clean_iter) {
-                                        break;
-                                    }
-                                    let path = model.get_path(&clean_iter).unwrap();
-                                    // Check if this is a category
-                                    if path.get_depth() == 1 {
-                                        let cat : Result<Option<String>,_> = model.get_value(&clean_iter,0).get();
-                                        if cat == Ok(Some(task.category.clone())) {
-                                            // Get to its children
-                                            wanted_parent = Some(clean_iter);
-                                            break;
-                                            
-                                        }
-                                    }
-                                }
-                                match wanted_parent {
-                                    Some(new_pos) => {
-                                        let _ = model.remove(&iter);
-                                        let select = model.insert_with_values(Some(&new_pos), None, &[0],&[&task.formatted(true)]);
-                                        clone.tasks_list.get_selection().select_iter(&select);
-                                    },
-                                    None => {
-                                        // we need to create a new category
-                                        let cat = &task.category;
-                                        let parent = model.insert_with_values(None, None, &[0],&[&cat]);
-
-                                        let _ = model.remove(&iter);
-                                        let select = model.insert_with_values(Some(&parent), None, &[0],&[&task.formatted(true)]);
-                                        clone.tasks_list.get_selection().select_iter(&select);
-                                    }
-                                }
+                        if let Some(task) = manager.tasks.get(index) {
+                            if placement != clone.task_groups(task) {
+                                // move the task to its new group, creating it if needed
+                                let new_pos = get_or_insert_group(model, &clone.task_groups(task));
+                                let select = insert_task_row(model, Some(&new_pos), index, task);
+                                // Removing the old row also takes care of empty parents in `connect_row_deleted`
+                                let _ = model.remove(iter);
+                                // A freshly made category won't show up through the filter until refiltering
+                                clone.refilter();
+                                clone.select_row(&select);
                             }
                         }
                     },
                     None => {}
                 }
-            
-                // Check if the parent needs to be removed
-                let parent_iter = model.get_iter(&parent_path).expect("couldnt get path");
-                if model.iter_n_children(Some(&parent_iter)) == 0 {
-                    let model : gtk::TreeStore = unsafe {
-                        model.clone().unsafe_cast()
-                    };
-                    model.remove(&parent_iter);
-                }
             }
         });
 
-        model.connect_row_deleted(|model, path| {
-            let parent_path = {
-                let mut path = path.clone();
-                path.up();
-                path
-            };
-            let iter = model.get_iter(&parent_path);
-            if let Some(iter) = iter {
-                if model.iter_n_children(Some(&iter)) == 0 {
-                    let model : gtk::TreeStore = unsafe {
-                        model.clone().unsafe_cast()
-                    };
-                    model.remove(&iter);
+        self.tasks_store.connect_row_deleted(|model, path| {
+            prune_empty_group(model, path);
+        });
+
+        // Connect the autosave menu, starting from whatever was picked last time
+        let modes = [
+            (&self.autosave_off, Autosave::Off),
+            (&self.autosave_field, Autosave::FieldChange),
+            (&self.autosave_selection, Autosave::SelectionChange),
+            (&self.autosave_timed, Autosave::Timed),
+        ];
+        for (item, mode) in modes.iter() {
+            if self.settings.borrow().autosave == *mode {
+                item.set_active(true);
+            }
+            let settings = Rc::clone(&self.settings);
+            let mode = *mode;
+            item.connect_toggled(move |item| {
+                if item.get_active() {
+                    let mut settings = settings.borrow_mut();
+                    settings.autosave = mode;
+                    let _ = settings.save();
                 }
+            });
+        }
+
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        let interval = self.settings.borrow().autosave_interval;
+        glib::timeout_add_seconds_local(interval, move || {
+            if clone.form_dirty() && clone.settings.borrow().autosave == Autosave::Timed {
+                clone.apply_form(&tclone);
             }
+            glib::Continue(true)
+        });
 
+        // Connect the reminders, checking every now and then
+        self.connect_reminders(&tasks);
+
+        // Ask before closing with anything unsaved
+        let clone = self.clone();
+        let tclone = tasks.clone();
+        self.main_window.connect_delete_event(move |_, _| {
+            gtk::Inhibit(!clone.settle_file(&tclone, "Close Without Saving"))
         });
+
+        // Reload when someone else changes the task file, or the todo.txt mirror
+        self.watch_file(&tasks);
+        self.watch_mirror(&tasks);
     }
 }
 
-fn get_layout_from_builder(builder : &gtk::Builder) -> UILayout {
+fn get_layout_from_builder(builder : &gtk::Builder, app : &gtk::Application) -> UILayout {
+    let tasks_store = gtk::TreeStore::new(&[
+        glib::Type::String, // name
+        glib::Type::String, // sub category
+        glib::Type::I64,    // due(sort)
+        glib::Type::String, // due(text)
+        glib::Type::U32,    // priority
+        glib::Type::Bool,   // done
+        glib::Type::I64,    // task index
+        glib::Type::String, // category
+    ]);
+    let filter_model = gtk::TreeModelFilter::new(&tasks_store, None);
+    let sort_model = gtk::TreeModelSort::new(&filter_model);
+    let settings = Settings::load();
+
     UILayout {
+        app : app.clone(),
         main_window : builder.get_object("main_window").expect("main_window is missing"),
-        save : builder.get_object("btn_save").expect("btn_save is missing"),
-        new : builder.get_object("btn_new").expect("btn_new is missing"),
-        delete : builder.get_object("btn_delete").expect("btn_delete is missing"),
-        remove_done : builder.get_object("btn_rmdone").expect("btn_rmdone is missing"),
+        quick_add : builder.get_object("quick_add").expect("quick_add is missing"),
+        menu_new_list : builder.get_object("menu_new_list").expect("menu_new_list is missing"),
+        menu_open : builder.get_object("menu_open").expect("menu_open is missing"),
+        menu_save_as : builder.get_object("menu_save_as").expect("menu_save_as is missing"),
+        menu_import_csv : builder.get_object("menu_import_csv").expect("menu_import_csv is missing"),
+        menu_export_csv : builder.get_object("menu_export_csv").expect("menu_export_csv is missing"),
+        menu_import_ical : builder.get_object("menu_import_ical").expect("menu_import_ical is missing"),
+        menu_export_ical : builder.get_object("menu_export_ical").expect("menu_export_ical is missing"),
+        menu_import_todo_txt : builder.get_object("menu_import_todo_txt").expect("menu_import_todo_txt is missing"),
+        menu_export_todo_txt : builder.get_object("menu_export_todo_txt").expect("menu_export_todo_txt is missing"),
+        menu_mirror_todo_txt : builder.get_object("menu_mirror_todo_txt").expect("menu_mirror_todo_txt is missing"),
+        menu_export_report : builder.get_object("menu_export_report").expect("menu_export_report is missing"),
+        menu_restore_backup : builder.get_object("menu_restore_backup").expect("menu_restore_backup is missing"),
+        recent_menu : builder.get_object("recent_menu").expect("recent_menu is missing"),
+        autosave_off : builder.get_object("autosave_off").expect("autosave_off is missing"),
+        autosave_field : builder.get_object("autosave_field").expect("autosave_field is missing"),
+        autosave_selection : builder.get_object("autosave_selection").expect("autosave_selection is missing"),
+        autosave_timed : builder.get_object("autosave_timed").expect("autosave_timed is missing"),
+        views : builder.get_object("views").expect("views is missing"),
         tasks_list : builder.get_object("tasks_list").expect("tasks_list is missing"),
+        calendar : CalendarView::new(&builder.get_object("calendar_view").expect("calendar_view is missing")),
+        board : BoardView::new(&builder.get_object("board_view").expect("board_view is missing"), settings.board_columns),
+        save_infobar : builder.get_object("save_infobar").expect("save_infobar is missing"),
+        save_error : builder.get_object("save_error_label").expect("save_error_label is missing"),
         task_grid : builder.get_object("task_grid").expect("task_grid is missing"),
+        due_label : builder.get_object("due_label").expect("due_label is missing"),
+        prio_label : builder.get_object("prio_label").expect("prio_label is missing"),
         name : builder.get_object("task_name").expect("task_name is missing"),
         category : builder.get_object("task_cat").expect("task_cat is missing"),
         sub_cat : builder.get_object("task_sub").expect("task_sub is missing"),
@@ -464,31 +2633,264 @@ fn get_layout_from_builder(builder : &gtk::Builder) -> UILayout {
         date : builder.get_object("task_date").expect("task_date is  missing"),
         done : builder.get_object("task_done").expect("task_done is missing"),
         prio : builder.get_object("task_prio").expect("task_prio is missing"),
+        search : builder.get_object("search_entry").expect("search_entry is missing"),
+        filter_hide_done : builder.get_object("filter_hide_done").expect("filter_hide_done is missing"),
+        filter_overdue : builder.get_object("filter_overdue").expect("filter_overdue is missing"),
+        filter_week : builder.get_object("filter_week").expect("filter_week is missing"),
+        filter_prio_on : builder.get_object("filter_prio_on").expect("filter_prio_on is missing"),
+        grouping : builder.get_object("grouping").expect("grouping is missing"),
+        filter_prio : builder.get_object("filter_prio").expect("filter_prio is missing"),
+        tasks_store,
+        filter_model,
+        sort_model,
+        settings : Rc::new(RefCell::new(settings)),
+        task_filter : Rc::new(RefCell::new(TaskFilter::default())),
+        history : Rc::new(RefCell::new(History::default())),
+        file : Rc::new(RefCell::new(TaskFile::default())),
+        monitor : Rc::new(RefCell::new(None)),
+        mirror_monitor : Rc::new(RefCell::new(None)),
+        mirrored : Rc::new(RefCell::new(None)),
+        form_tasks : Rc::new(RefCell::new(Vec::new())),
+        form_touched : Rc::new(Cell::new(Touched::default())),
+        filling_form : Rc::new(Cell::new(false)),
+        autosave_pending : Rc::new(RefCell::new(None)),
+        reminders : Rc::new(RefCell::new(Scheduler::default())),
+        hide_done_outside_agenda : Rc::new(Cell::new(None)),
+    }
+}
+
+/// Index of the task a reminder's `index:name` target is about, if it is still around
+fn find_reminded_task(tasks : &TasksManager, target : &str) -> Option<usize> {
+    let mut parts = target.splitn(2, ':');
+    let index : usize = parts.next()?.parse().ok()?;
+    let name = parts.next()?;
+    match tasks.tasks.get(index) {
+        Some(task) if task.name == name => Some(index),
+        // Tasks before it were removed or moved since
+        _ => tasks.tasks.iter().position(|task| task.name == name),
+    }
+}
+
+/// Values for every column in `TASK_COLUMNS` describing the task at `index`
+fn task_row_values(index : usize, task : &Task) -> Vec<glib::Value> {
+    vec![
+        task.name.to_value(),
+        task.sub_category.to_value(),
+        dates::due_days(&task.due).to_value(),
+        dates::format_due(&task.due).to_value(),
+        (task.priority as u32).to_value(),
+        task.done.to_value(),
+        (index as i64).to_value(),
+        task.category.to_value(),
+    ]
+}
+
+/// Inserts a task row without emitting `row-changed`
+fn insert_task_row(model : &gtk::TreeStore, parent : Option<&TreeIter>, index : usize, task : &Task) -> TreeIter {
+    let values = task_row_values(index, task);
+    let values : Vec<&dyn ToValue> = values.iter().map(|v| v as &dyn ToValue).collect();
+    model.insert_with_values(parent, None, &TASK_COLUMNS, &values)
+}
+
+/// Overwrites an existing task row with the values from `task_row_values`
+fn set_task_row(model : &gtk::TreeStore, iter : &TreeIter, values : &[glib::Value]) {
+    let values : Vec<&dyn ToValue> = values.iter().map(|v| v as &dyn ToValue).collect();
+    model.set(iter, &TASK_COLUMNS, &values);
+}
+
+/// Returns the index in `TasksManager::tasks` of the task the row points to, `None` for group rows
+fn get_task_index(model : &TreeModel, iter : &TreeIter) -> Option<usize> {
+    let index : Result<i64,_> = model.get_value(iter, COL_TASK as i32).get_some();
+    match index {
+        Ok(i) if i >= 0 => Some(i as usize),
+        _ => None,
+    }
+}
+
+/// Updates the stored indices after the task at `removed` was taken out of `TasksManager::tasks`
+fn shift_task_indices(model : &gtk::TreeStore, removed : usize) {
+    let store = model.clone();
+    model.foreach(move |model, _, iter| {
+        if let Some(i) = get_task_index(model, iter) {
+            if i > removed {
+                store.set_value(iter, COL_TASK, &((i - 1) as i64).to_value());
+            }
+        }
+        false
+    });
+}
+
+/// Finds the row of the task at `index` in `TasksManager::tasks`
+fn find_task_row(model : &gtk::TreeStore, index : usize) -> Option<TreeIter> {
+    let mut found = None;
+    model.foreach(|model, _, iter| {
+        if get_task_index(model, iter) == Some(index) {
+            found = Some(iter.clone());
+            return true;
+        }
+        false
+    });
+    found
+}
+
+/// Finds the group(category or sub category) row named `name` directly under `parent`
+fn find_group_row(model : &gtk::TreeStore, parent : Option<&TreeIter>, name : &str) -> Option<TreeIter> {
+    let iter = model.iter_children(parent)?;
+    loop {
+        if get_task_index(model.upcast_ref(), &iter).is_none() {
+            let row : Result<Option<String>,_> = model.get_value(&iter, COL_NAME as i32).get();
+            if row == Ok(Some(name.to_string())) {
+                return Some(iter);
+            }
+        }
+        if !model.iter_next(&iter) {
+            return None;
+        }
+    }
+}
+
+/// Finds the group row at `groups`(names from the top level down), inserting whichever are missing
+fn get_or_insert_group(model : &gtk::TreeStore, groups : &[String]) -> TreeIter {
+    let mut parent : Option<TreeIter> = None;
+    for name in groups.iter() {
+        let row = match find_group_row(model, parent.as_ref(), name) {
+            Some(row) => row,
+            None => model.insert_with_values(parent.as_ref(), None, &[COL_NAME, COL_TASK], &[name, &NO_TASK]),
+        };
+        parent = Some(row);
     }
+    parent.expect("every task is in a group")
 }
 
-/// Returns the formatted task and category - (Option(task_formatted),Option<cat>)
-fn get_selected_data(model : &TreeModel, iter :&TreeIter) -> (Option<String>,Option<String>) {
-    let mut path = model.get_path(&iter).expect("couldnt get path from iter");
+/// Names of the group rows above a task row, from the top level down
+fn get_row_groups(model : &TreeModel, iter : &TreeIter) -> Vec<String> {
+    let mut groups = Vec::new();
+    let mut row = model.iter_parent(iter);
+    while let Some(parent) = row {
+        groups.insert(0, get_row_text(model, &parent, COL_NAME));
+        row = model.iter_parent(&parent);
+    }
+    groups
+}
 
-    if path.get_depth() > 1 {
-        let _ = path.up();
-        let cat = model.get_iter(&path).expect("couldnt get category");
-        let cat : Result<Option<String>,_> = model.get_value(&cat, 0).get();
-        let formatted : Result<Option<String>,_> = model.get_value(&iter,0).get();
+/// Text of a string column, empty if it isn't set
+fn get_row_text(model : &TreeModel, iter : &TreeIter, column : u32) -> String {
+    let value : Result<Option<String>,_> = model.get_value(iter, column as i32).get();
+    value.ok().flatten().unwrap_or_default()
+}
 
-        // Make sure we have both a normal task and category
-        if formatted.is_ok() && cat.is_ok() {
-            return (formatted.unwrap(), cat.unwrap());
+/// Open and overdue tasks under a group row, `today` is in days since the epoch
+fn count_tasks(model : &TreeModel, iter : &TreeIter, today : i64) -> (usize, usize) {
+    let (mut open, mut overdue) = (0, 0);
+    let child = match model.iter_children(Some(iter)) {
+        Some(child) => child,
+        None => return (0, 0),
+    };
+    loop {
+        if get_task_index(model, &child).is_some() {
+            let done = model.get_value(&child, COL_DONE as i32).get_some().unwrap_or(false);
+            let due = model.get_value(&child, COL_DUE as i32).get_some().unwrap_or(dates::NO_DUE);
+            if !done {
+                open += 1;
+                if due < today {
+                    overdue += 1;
+                }
+            }
+        }
+        else {
+            let (sub_open, sub_overdue) = count_tasks(model, &child, today);
+            open += sub_open;
+            overdue += sub_overdue;
+        }
+        if !model.iter_next(&child) {
+            return (open, overdue);
         }
+    }
+}
+
+/// `#rrggbb` for pango markup
+fn color_hex(color : &gdk::RGBA) -> String {
+    let channel = |c : f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(color.red), channel(color.green), channel(color.blue))
+}
 
+/// Agenda bucket of `task`, `today` is in days since the epoch
+fn task_bucket(task : &Task, today : i64) -> Bucket {
+    Bucket::of(dates::due_days(&task.due), task.done, today)
+}
+
+/// Category and sub category a task row is currently placed under in the tree
+fn get_row_placement(model : &TreeModel, iter : &TreeIter) -> (String, String) {
+    let name = |iter : &TreeIter| -> String {
+        let name : Result<Option<String>,_> = model.get_value(iter, COL_NAME as i32).get();
+        name.ok().flatten().unwrap_or_default()
+    };
+    match model.iter_parent(iter) {
+        Some(parent) => match model.iter_parent(&parent) {
+            Some(cat) => (name(&cat), name(&parent)),
+            None => (name(&parent), String::new()),
+        },
+        None => (String::new(), String::new()),
+    }
+}
+
+/// Category and sub category of a task dropped onto the category(or sub category) row `iter`
+fn get_group_placement(model : &TreeModel, iter : &TreeIter) -> (String, String) {
+    let name = |iter : &TreeIter| -> String {
+        let name : Result<Option<String>,_> = model.get_value(iter, COL_NAME as i32).get();
+        name.ok().flatten().unwrap_or_default()
+    };
+    match model.iter_parent(iter) {
+        Some(cat) => (name(&cat), name(iter)),
+        None => (name(iter), String::new()),
+    }
+}
+
+/// Removes the parent of a deleted row if it was the last child, which in turn
+/// emits `row-deleted` for the parent so an emptied category goes away as well
+fn prune_empty_group(model : &gtk::TreeStore, path : &gtk::TreePath) {
+    let mut parent_path = path.clone();
+    if !parent_path.up() || parent_path.get_depth() == 0 {
+        return;
+    }
+    if let Some(iter) = model.get_iter(&parent_path) {
+        if get_task_index(model.upcast_ref(), &iter).is_none() && model.iter_n_children(Some(&iter)) == 0 {
+            model.remove(&iter);
+        }
     }
-    (None,None)
 }
 
-fn find_task_in_list<'a>(task_list : &'a Vec<Task>, cat : &str, formatted_task : &str) -> Option<&'a Task> {
-    task_list.iter().find(|t| t.category == cat && t.formatted(true) == formatted_task)
+/// Whether a row of `tasks_store` passes the filter, group rows pass if any task under them does
+fn is_row_visible(model : &TreeModel, iter : &TreeIter, task_filter : &TaskFilter, today : i64) -> bool {
+    if get_task_index(model, iter).is_some() {
+        let text = |col : u32| -> String {
+            let value : Result<Option<String>,_> = model.get_value(iter, col as i32).get();
+            value.ok().flatten().unwrap_or_default()
+        };
+        let category = text(COL_CAT);
+        let name = text(COL_NAME);
+        let sub_category = text(COL_SUB);
+
+        let row = FilterRow {
+            name : &name,
+            category : &category,
+            sub_category : &sub_category,
+            due : model.get_value(iter, COL_DUE as i32).get_some().unwrap_or(dates::NO_DUE),
+            priority : model.get_value(iter, COL_PRIO as i32).get_some().unwrap_or(0),
+            done : model.get_value(iter, COL_DONE as i32).get_some().unwrap_or(false),
+        };
+        return task_filter.matches(&row, today);
+    }
+
+    match model.iter_children(Some(iter)) {
+        Some(child) => loop {
+            if is_row_visible(model, &child, task_filter, today) {
+                return true;
+            }
+            if !model.iter_next(&child) {
+                return false;
+            }
+        },
+        None => false,
+    }
 }
-fn find_task_in_list_mut<'a>(task_list : &'a mut Vec<Task>, cat : &str, formatted_task : &str) -> Option<&'a mut Task> {
-    task_list.iter_mut().find(|t| t.category == cat && t.formatted(true) == formatted_task)
-}
\ No newline at end of file
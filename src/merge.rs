@@ -0,0 +1,323 @@
+//! Three way merge of task lists, for when the task file changed under our feet,
+//! and matching tasks brought in from other formats to the ones we have
+
+use crate::dates;
+use crate::undo::TaskData;
+
+/// What bringing in tasks from elsewhere(an imported file, an edited todo.txt mirror) does to the tasks
+#[derive(Default)]
+pub struct Import {
+    /// Index of a task we already have and what it becomes
+    pub changed : Vec<(usize, TaskData)>,
+    /// Indices of tasks which are gone, in order
+    pub removed : Vec<usize>,
+    pub added : Vec<TaskData>,
+}
+
+/// Whether `a` and `b` are the same task, going by name(however it's spaced), category and sub category
+pub fn same_task(a : &TaskData, b : &TaskData) -> bool {
+    a.category == b.category
+        && a.sub_category == b.sub_category
+        && a.name.split_whitespace().eq(b.name.split_whitespace())
+}
+
+/// Matches `incoming` to the tasks in `existing`, by the index a task comes with(for formats with ids of their own)
+/// or else by the first one it is the `same_task` as. Matched tasks take on what came in, the rest get added
+pub fn import(existing : &[TaskData], incoming : Vec<(Option<usize>, TaskData)>) -> Import {
+    let mut import = Import::default();
+    let mut matched = vec![false; existing.len()];
+    for (index, mut task) in incoming.into_iter() {
+        let index = index
+            .filter(|index| *index < existing.len() && !matched[*index])
+            .or_else(|| (0..existing.len()).find(|index| !matched[*index] && same_task(&existing[*index], &task)));
+
+        match index {
+            Some(index) => {
+                matched[index] = true;
+                // Formats which can't hold every space don't get to change them
+                if task.name.split_whitespace().eq(existing[index].name.split_whitespace()) {
+                    task.name = existing[index].name.clone();
+                }
+                if !existing[index].same_as(&task) {
+                    import.changed.push((index, task));
+                }
+            }
+            None => import.added.push(task),
+        }
+    }
+    import
+}
+
+/// Which task of `new` each task of `old` became, `None` if there is none, for lists with
+/// edits in between(like a backup and the tasks now). Tasks which are the `same_task` are paired in
+/// order first, then wherever they moved to, then in each stretch between those a task with the
+/// same name(which was moved to another category) or else the same categories(which was renamed)
+pub fn align(old : &[TaskData], new : &[TaskData]) -> Vec<Option<usize>> {
+    // Longest common subsequence, `lengths[i][j]` is its length for `old[i..]` and `new[j..]`
+    let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if same_task(&old[i], &new[j]) { lengths[i + 1][j + 1] + 1 } else { lengths[i + 1][j].max(lengths[i][j + 1]) };
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if same_task(&old[i], &new[j]) {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        }
+        else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        }
+        else {
+            j += 1;
+        }
+    }
+    pairs.push((old.len(), new.len()));
+
+    let mut aligned : Vec<Option<usize>> = vec![None; old.len()];
+    let mut used = vec![false; new.len()];
+    for (i, j) in pairs.iter().filter(|(i, _)| *i < old.len()) {
+        aligned[*i] = Some(*j);
+        used[*j] = true;
+    }
+    // Moved somewhere else
+    for (task, a) in old.iter().zip(aligned.iter_mut()).filter(|(_, a)| a.is_none()) {
+        if let Some(n) = (0..new.len()).find(|n| !used[*n] && same_task(task, &new[*n])) {
+            used[n] = true;
+            *a = Some(n);
+        }
+    }
+    let alikes : [fn(&TaskData, &TaskData) -> bool; 2] = [
+        |a, b| a.name.split_whitespace().eq(b.name.split_whitespace()),
+        |a, b| a.category == b.category && a.sub_category == b.sub_category,
+    ];
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in pairs.into_iter() {
+        for alike in alikes.iter() {
+            for (task, a) in old[i..next_i].iter().zip(aligned[i..next_i].iter_mut()).filter(|(_, a)| a.is_none()) {
+                if let Some(n) = (j..next_j).find(|n| !used[*n] && alike(task, &new[*n])) {
+                    used[n] = true;
+                    *a = Some(n);
+                }
+            }
+        }
+        i = next_i + 1;
+        j = next_j + 1;
+    }
+    aligned
+}
+
+/// Where each task of `base` went in `side`, `None` if it was removed.
+/// Tasks have no id, so an unchanged task matches first, then one with the same name(which had its other
+/// fields edited). A renamed task counts as one removed and another added
+fn match_base(base : &[TaskData], side : &[TaskData]) -> Vec<Option<usize>> {
+    let mut matches : Vec<Option<usize>> = vec![None; base.len()];
+    let mut used = vec![false; side.len()];
+    for (task, m) in base.iter().zip(matches.iter_mut()) {
+        if let Some(i) = (0..side.len()).find(|i| !used[*i] && side[*i].same_as(task)) {
+            used[i] = true;
+            *m = Some(i);
+        }
+    }
+    for (task, m) in base.iter().zip(matches.iter_mut()).filter(|(_, m)| m.is_none()) {
+        if let Some(i) = (0..side.len()).find(|i| !used[*i] && side[*i].name.trim() == task.name.trim()) {
+            used[i] = true;
+            *m = Some(i);
+        }
+    }
+    matches
+}
+
+/// A task both sides kept, field by field whatever side changed it. Ours win when both changed the same field
+pub fn merge_task(base : &TaskData, ours : &TaskData, theirs : &TaskData) -> TaskData {
+    fn pick<'a, T : PartialEq>(base : &T, ours : &'a T, theirs : &'a T) -> &'a T {
+        if ours != base { ours } else { theirs }
+    }
+    let due = if dates::due_days(&ours.due) != dates::due_days(&base.due) { ours.due } else { theirs.due };
+    TaskData {
+        name : pick(&base.name, &ours.name, &theirs.name).clone(),
+        category : pick(&base.category, &ours.category, &theirs.category).clone(),
+        sub_category : pick(&base.sub_category, &ours.sub_category, &theirs.sub_category).clone(),
+        due,
+        done : *pick(&base.done, &ours.done, &theirs.done),
+        priority : *pick(&base.priority, &ours.priority, &theirs.priority),
+    }
+}
+
+/// Their tasks with our changes since `base` on top, in their order with what we added at the end.
+/// A task removed on one side and edited on the other is kept, edited
+pub fn merge(base : &[TaskData], ours : &[TaskData], theirs : &[TaskData]) -> Vec<TaskData> {
+    let in_ours = match_base(base, ours);
+    let in_theirs = match_base(base, theirs);
+
+    let mut merged : Vec<TaskData> = Vec::new();
+    let mut from_theirs : Vec<Option<usize>> = vec![None; theirs.len()];
+    for (b, t) in in_theirs.iter().enumerate() {
+        if let Some(t) = t {
+            from_theirs[*t] = Some(b);
+        }
+    }
+    for (task, b) in theirs.iter().zip(from_theirs.iter()) {
+        match b {
+            // Added by them
+            None => merged.push(task.clone()),
+            Some(b) => match in_ours[*b] {
+                Some(o) => merged.push(merge_task(&base[*b], &ours[o], task)),
+                // Removed by us, unless they have changed it since
+                None => {
+                    if !task.same_as(&base[*b]) {
+                        merged.push(task.clone());
+                    }
+                }
+            },
+        }
+    }
+
+    let mut ours_used = vec![false; ours.len()];
+    for (b, o) in in_ours.iter().enumerate() {
+        if let Some(o) = o {
+            ours_used[*o] = true;
+            // Removed by them, unless we have changed it since
+            if in_theirs[b].is_none() && !ours[*o].same_as(&base[b]) {
+                merged.push(ours[*o].clone());
+            }
+        }
+    }
+    for (task, _) in ours.iter().zip(ours_used.iter()).filter(|(_, used)| !**used) {
+        // They might have made the very same change
+        if !merged.iter().any(|t| t.same_as(task)) {
+            merged.push(task.clone());
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cool_organizer::*;
+
+    fn task(name : &str, category : &str, priority : u8) -> TaskData {
+        let mut task = TaskData::from_task(&Task::new(name));
+        task.category = category.to_string();
+        task.sub_category = String::new();
+        task.due = None;
+        task.done = false;
+        task.priority = priority;
+        task
+    }
+
+    fn names(tasks : &[TaskData]) -> Vec<&str> {
+        tasks.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    #[test]
+    fn both_add_and_remove() {
+        let base = vec![task("a", "", 0), task("b", "", 0), task("c", "", 0)];
+        let ours = vec![task("a", "", 0), task("c", "", 0), task("mine", "", 0)];
+        let theirs = vec![task("theirs", "", 0), task("a", "", 0), task("b", "", 0)];
+
+        assert_eq!(names(&merge(&base, &ours, &theirs)), ["theirs", "a", "mine"]);
+    }
+
+    #[test]
+    fn different_fields_changed() {
+        let base = vec![task("a", "home", 1), task("b", "", 0)];
+        let mut ours = base.clone();
+        ours[0].priority = 5;
+        let mut theirs = base.clone();
+        theirs[0].done = true;
+        theirs[0].category = "work".to_string();
+
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(names(&merged), ["a", "b"]);
+        assert_eq!(merged[0].priority, 5);
+        assert!(merged[0].done);
+        assert_eq!(merged[0].category, "work");
+    }
+
+    #[test]
+    fn same_field_changed() {
+        let base = vec![task("a", "", 1)];
+        let mut ours = base.clone();
+        ours[0].priority = 5;
+        let mut theirs = base.clone();
+        theirs[0].priority = 3;
+
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(names(&merged), ["a"]);
+        assert_eq!(merged[0].priority, 5);
+
+        // The very same change
+        let merged = merge(&base, &ours, &ours);
+        assert_eq!(names(&merged), ["a"]);
+        assert_eq!(merged[0].priority, 5);
+    }
+
+    #[test]
+    fn removed_and_edited() {
+        let base = vec![task("a", "", 1), task("b", "", 1)];
+        let mut ours = vec![task("a", "", 1)];
+        let mut theirs = vec![task("b", "", 1)];
+        // Nobody edited, so both removals stand
+        assert!(merge(&base, &ours, &theirs).is_empty());
+
+        ours[0].priority = 4;
+        theirs[0].done = true;
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(names(&merged), ["b", "a"]);
+        assert!(merged[0].done);
+        assert_eq!(merged[1].priority, 4);
+    }
+
+    #[test]
+    fn importing() {
+        let existing = vec![task("a", "work", 0), task("b  c", "", 0), task("b c", "", 0)];
+        let mut a = task("a", "work", 3);
+        a.done = true;
+        let incoming = vec![
+            (None, a),
+            // Spaced differently, the first one which isn't taken yet
+            (None, task("b c", "", 0)),
+            (None, task("b c", "", 0)),
+            (Some(0), task("d", "", 0)),
+            (Some(7), task("e", "", 0)),
+        ];
+        let import = import(&existing, incoming);
+        assert_eq!(import.changed.len(), 1);
+        assert_eq!(import.changed[0].0, 0);
+        assert_eq!(import.changed[0].1.priority, 3);
+        assert!(import.removed.is_empty());
+        // Index 0 was taken already and 7 isn't a task
+        assert_eq!(names(&import.added), ["d", "e"]);
+    }
+
+    #[test]
+    fn aligning() {
+        let old = vec![task("a", "work", 0), task("b", "work", 0), task("c", "home", 0), task("d", "home", 0), task("e", "", 0)];
+        let new = vec![
+            task("new", "", 0),
+            task("a", "work", 2),
+            task("b renamed", "work", 0),
+            task("d", "home", 0),
+            task("c", "home", 0),
+            task("f", "home", 0),
+        ];
+        // `a` was edited and `b` renamed in the same stretch, `c` moved after `d` and `e` is gone
+        assert_eq!(align(&old, &new), [Some(1), Some(2), Some(4), Some(3), None]);
+        assert_eq!(align(&old, &[]), [None; 5]);
+    }
+
+    #[test]
+    fn renamed_is_removed_and_added() {
+        let base = vec![task("a", "", 0)];
+        let ours = vec![task("a renamed", "", 0)];
+        let theirs = base.clone();
+
+        assert_eq!(names(&merge(&base, &ours, &theirs)), ["a renamed"]);
+    }
+}
@@ -0,0 +1,13 @@
+//! What the priorities of tasks go up to and how they look
+
+/// Same as the priority spin button goes, 0 being no priority
+pub const MAX_PRIORITY : u8 = 8;
+
+/// CSS class for a priority, the actual colour is up to the style sheet(and through it the theme)
+pub fn priority_class(priority : u32) -> &'static str {
+    match priority {
+        0..=2 => "priority-low",
+        3..=5 => "priority-medium",
+        _ => "priority-high",
+    }
+}
@@ -0,0 +1,222 @@
+//! Parsing the quick add entry, `Fix login bug @work/backend !3 due:friday`
+//!
+//! - `@category` or `@category/sub category`
+//! - `!priority`, from 0 up to `MAX_PRIORITY`
+//! - `due:` followed by `today`, `tomorrow`, a weekday(the next one, never today), `+3d`/`+2w` or `YYYY-MM-DD`
+//! - every other word is part of the name
+
+use crate::dates;
+use crate::priority::MAX_PRIORITY;
+use std::fmt;
+
+const WEEKDAYS : [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+/// What the entry described, anything left out is up to the task's defaults
+#[derive(Debug, PartialEq)]
+pub struct QuickTask {
+    pub name : String,
+    pub category : Option<String>,
+    pub sub_category : Option<String>,
+    pub priority : Option<u8>,
+    /// Days since the epoch
+    pub due : Option<i64>,
+}
+
+/// What is wrong and where, `start..end` is a byte range of the input
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub message : String,
+    pub start : usize,
+    pub end : usize,
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// `today` is in days since the epoch, relative due dates count from it
+pub fn parse(input : &str, today : i64) -> Result<QuickTask, ParseError> {
+    let mut task = QuickTask {
+        name : String::new(),
+        category : None,
+        sub_category : None,
+        priority : None,
+        due : None,
+    };
+    let mut name : Vec<&str> = Vec::new();
+
+    for (start, word) in words(input) {
+        let end = start + word.len();
+        let error = |message : String| ParseError { message, start, end };
+
+        if let Some(place) = word.strip_prefix('@') {
+            let (category, sub) = match place.find('/') {
+                Some(i) => (&place[..i], Some(&place[i + 1..])),
+                None => (place, None),
+            };
+            if category.is_empty() {
+                return Err(error("@ needs a category after it, like @work or @work/backend".to_string()));
+            }
+            if task.category.is_some() {
+                return Err(error("only one @category per task".to_string()));
+            }
+            task.category = Some(category.to_string());
+            task.sub_category = sub.filter(|sub| !sub.is_empty()).map(str::to_string);
+        }
+        else if let Some(prio) = word.strip_prefix('!') {
+            match prio.parse::<u8>() {
+                Ok(prio) if prio <= MAX_PRIORITY => task.priority = Some(prio),
+                _ => return Err(error(format!("priority has to be a number from 0 to {}", MAX_PRIORITY))),
+            }
+        }
+        else if let Some(due) = word.strip_prefix("due:") {
+            match parse_due(&due.to_lowercase(), today) {
+                Some(due) => task.due = Some(due),
+                None => return Err(error(format!("\"{}\" isn't a date, try today, friday, +3d or 2024-12-31", due))),
+            }
+        }
+        else {
+            name.push(word);
+        }
+    }
+
+    if name.is_empty() {
+        return Err(ParseError { message : "the task needs a name".to_string(), start : 0, end : input.len() });
+    }
+    task.name = name.join(" ");
+    Ok(task)
+}
+
+/// The words of `input` along with the byte they start at
+fn words(input : &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in input.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                words.push((s, &input[s..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => (),
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, &input[s..]));
+    }
+    words
+}
+
+/// Days since the epoch for whatever follows `due:`, already lowercased
+fn parse_due(due : &str, today : i64) -> Option<i64> {
+    match due {
+        "today" => return Some(today),
+        "tomorrow" => return Some(today + 1),
+        _ => (),
+    }
+
+    // Weekdays, spelled out or shortened down to 3 letters
+    if due.len() >= 3 {
+        if let Some(day) = WEEKDAYS.iter().position(|name| name.starts_with(due)) {
+            let ahead = (day as i64 - dates::weekday(today)).rem_euclid(7);
+            return Some(today + if ahead == 0 { 7 } else { ahead });
+        }
+    }
+
+    if let Some(offset) = due.strip_prefix('+') {
+        let unit = offset.chars().last()?;
+        let count = &offset[..offset.len() - unit.len_utf8()];
+        // `parse` would take `+3` and `-3` too
+        if !count.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let count : i64 = count.parse().ok()?;
+        let days = match unit {
+            'd' => Some(count),
+            'w' => count.checked_mul(7),
+            _ => None,
+        };
+        return days.and_then(|days| today.checked_add(days)).filter(|due| dates::in_range(*due));
+    }
+
+    dates::parse_iso(due)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A wednesday
+    fn today() -> i64 {
+        dates::days_from_civil(2024, 1, 3)
+    }
+
+    #[test]
+    fn parses_everything() {
+        let task = parse("Fix login bug @work/backend !3 due:friday", today()).unwrap();
+        assert_eq!(task, QuickTask {
+            name : "Fix login bug".to_string(),
+            category : Some("work".to_string()),
+            sub_category : Some("backend".to_string()),
+            priority : Some(3),
+            due : Some(dates::days_from_civil(2024, 1, 5)),
+        });
+    }
+
+    #[test]
+    fn just_a_name() {
+        let task = parse("  Buy   milk ", today()).unwrap();
+        assert_eq!(task.name, "Buy milk");
+        assert_eq!(task.category, None);
+        assert_eq!(task.sub_category, None);
+        assert_eq!(task.priority, None);
+        assert_eq!(task.due, None);
+    }
+
+    #[test]
+    fn tokens_anywhere() {
+        let task = parse("!1 @home call mom due:tomorrow", today()).unwrap();
+        assert_eq!(task.name, "call mom");
+        assert_eq!(task.category.as_deref(), Some("home"));
+        assert_eq!(task.sub_category, None);
+        assert_eq!(task.priority, Some(1));
+        assert_eq!(task.due, Some(today() + 1));
+    }
+
+    #[test]
+    fn due_dates() {
+        let due = |s : &str| parse(&format!("x due:{}", s), today()).unwrap().due.unwrap();
+        assert_eq!(due("today"), today());
+        assert_eq!(due("Wed"), today() + 7);
+        assert_eq!(due("mon"), today() + 5);
+        assert_eq!(due("+3d"), today() + 3);
+        assert_eq!(due("+2w"), today() + 14);
+        assert_eq!(due("2024-02-29"), dates::days_from_civil(2024, 2, 29));
+
+        assert!(parse("x due:+-3d", today()).is_err());
+        assert!(parse("x due:++3d", today()).is_err());
+        assert!(parse("x due:+9223372036854775807d", today()).is_err());
+        assert!(parse("x due:+9223372036854775807w", today()).is_err());
+        assert!(parse("x due:+3000000d", today()).is_err());
+        assert!(parse("x due:99999-01-01", today()).is_err());
+    }
+
+    #[test]
+    fn errors_point_at_the_word() {
+        let input = "Fix bug !12 @work";
+        let error = parse(input, today()).unwrap_err();
+        assert_eq!(&input[error.start..error.end], "!12");
+
+        let input = "Fix bug due:2023-02-29";
+        let error = parse(input, today()).unwrap_err();
+        assert_eq!(&input[error.start..error.end], "due:2023-02-29");
+
+        let input = "Fix bug @/backend";
+        let error = parse(input, today()).unwrap_err();
+        assert_eq!(&input[error.start..error.end], "@/backend");
+
+        assert!(parse("due:fr @x", today()).is_err());
+        assert!(parse("@work !2", today()).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+//! Deciding when to remind about tasks, the notifications themselves are up to the ui
+
+use crate::dates;
+use std::collections::HashSet;
+
+/// Where the scheduler gets the time from, so tests can move it around
+pub trait Clock {
+    /// Today in days since the epoch, and the hour(0-23)
+    fn now(&self) -> (i64, u32);
+}
+
+/// The local time
+pub struct SystemClock;
+impl Clock for SystemClock {
+    fn now(&self) -> (i64, u32) {
+        let hour = glib::DateTime::new_now_local().get_hour();
+        (dates::today(), hour as u32)
+    }
+}
+
+/// When to remind, from the settings
+pub struct Config {
+    /// Remind about tasks due up to this many days ahead, 0 being just today
+    pub lead_days : u32,
+    /// Hour quiet hours start at, reminders wait until `quiet_end`. Equal hours mean no quiet hours
+    pub quiet_start : u32,
+    pub quiet_end : u32,
+}
+impl Config {
+    fn is_quiet(&self, hour : u32) -> bool {
+        if self.quiet_start <= self.quiet_end {
+            hour >= self.quiet_start && hour < self.quiet_end
+        }
+        else {
+            // Over midnight, like 22 to 8
+            hour >= self.quiet_start || hour < self.quiet_end
+        }
+    }
+}
+
+/// The parts of a task reminders care about
+pub struct TaskInfo<'a> {
+    /// Index in `TasksManager::tasks`
+    pub index : usize,
+    pub name : &'a str,
+    /// Days since the epoch, `dates::NO_DUE` when there is no due date
+    pub due : i64,
+    pub done : bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    /// Due within the lead time
+    DueSoon,
+    /// Its due date just went by
+    Overdue,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Reminder {
+    pub index : usize,
+    pub name : String,
+    pub due : i64,
+    pub kind : Kind,
+}
+
+#[derive(Default)]
+pub struct Scheduler {
+    /// Day of the last check which wasn't during quiet hours
+    last_day : Option<i64>,
+    /// Reminders already given, by name, due date and whether it was the overdue one.
+    /// Moving the due date makes it another reminder
+    given : HashSet<(String, i64, bool)>,
+}
+impl Scheduler {
+    /// What to remind about right now, each reminder is only returned once.
+    /// Nothing comes out during quiet hours, it all waits for the first check after them
+    pub fn check(&mut self, clock : &dyn Clock, config : &Config, tasks : &[TaskInfo]) -> Vec<Reminder> {
+        let (today, hour) = clock.now();
+        if config.is_quiet(hour) {
+            return Vec::new();
+        }
+        // The first check only counts yesterday's tasks as newly overdue, not every old one
+        let overdue_since = self.last_day.unwrap_or(today - 1).min(today - 1);
+        self.last_day = Some(today);
+
+        let mut reminders = Vec::new();
+        for task in tasks.iter().filter(|t| !t.done && t.due != dates::NO_DUE) {
+            let kind = if task.due >= today && task.due <= today + config.lead_days as i64 {
+                Kind::DueSoon
+            }
+            else if task.due < today && task.due >= overdue_since {
+                Kind::Overdue
+            }
+            else {
+                continue;
+            };
+            if self.given.insert((task.name.to_string(), task.due, kind == Kind::Overdue)) {
+                reminders.push(Reminder { index : task.index, name : task.name.to_string(), due : task.due, kind });
+            }
+        }
+        reminders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct FakeClock {
+        day : Cell<i64>,
+        hour : Cell<u32>,
+    }
+    impl Clock for FakeClock {
+        fn now(&self) -> (i64, u32) {
+            (self.day.get(), self.hour.get())
+        }
+    }
+
+    const DAY : i64 = 19725;
+
+    fn clock(day : i64, hour : u32) -> FakeClock {
+        FakeClock { day : Cell::new(day), hour : Cell::new(hour) }
+    }
+
+    fn config() -> Config {
+        Config { lead_days : 0, quiet_start : 22, quiet_end : 8 }
+    }
+
+    fn task(index : usize, name : &str, due : i64) -> TaskInfo<'_> {
+        TaskInfo { index, name, due, done : false }
+    }
+
+    fn kinds(reminders : &[Reminder]) -> Vec<(&str, Kind)> {
+        reminders.iter().map(|r| (r.name.as_str(), r.kind)).collect()
+    }
+
+    #[test]
+    fn due_today_once() {
+        let clock = clock(DAY, 12);
+        let mut scheduler = Scheduler::default();
+        let tasks = [task(0, "today", DAY), task(1, "tomorrow", DAY + 1), task(2, "undated", dates::NO_DUE)];
+
+        assert_eq!(kinds(&scheduler.check(&clock, &config(), &tasks)), [("today", Kind::DueSoon)]);
+        assert!(scheduler.check(&clock, &config(), &tasks).is_empty());
+    }
+
+    #[test]
+    fn lead_time() {
+        let clock = clock(DAY, 12);
+        let mut scheduler = Scheduler::default();
+        let config = Config { lead_days : 2, ..config() };
+        let tasks = [task(0, "a", DAY + 1), task(1, "b", DAY + 2), task(2, "c", DAY + 3)];
+
+        assert_eq!(kinds(&scheduler.check(&clock, &config, &tasks)), [("a", Kind::DueSoon), ("b", Kind::DueSoon)]);
+        clock.day.set(DAY + 1);
+        assert_eq!(kinds(&scheduler.check(&clock, &config, &tasks)), [("c", Kind::DueSoon)]);
+    }
+
+    #[test]
+    fn newly_overdue() {
+        let clock = clock(DAY, 12);
+        let mut scheduler = Scheduler::default();
+        let tasks = [task(0, "long ago", DAY - 30), task(1, "yesterday", DAY - 1), task(2, "today", DAY)];
+
+        // Old overdue tasks were surely noticed already
+        assert_eq!(kinds(&scheduler.check(&clock, &config(), &tasks)), [("yesterday", Kind::Overdue), ("today", Kind::DueSoon)]);
+        clock.day.set(DAY + 1);
+        assert_eq!(kinds(&scheduler.check(&clock, &config(), &tasks)), [("today", Kind::Overdue)]);
+        clock.day.set(DAY + 2);
+        assert!(scheduler.check(&clock, &config(), &tasks).is_empty());
+    }
+
+    #[test]
+    fn overdue_while_not_running() {
+        let clock = clock(DAY, 12);
+        let mut scheduler = Scheduler::default();
+        assert!(scheduler.check(&clock, &config(), &[]).is_empty());
+
+        // Three days of nothing being checked, everything that went overdue in between counts
+        clock.day.set(DAY + 3);
+        let tasks = [task(0, "before", DAY - 1), task(1, "between", DAY + 1)];
+        assert_eq!(kinds(&scheduler.check(&clock, &config(), &tasks)), [("between", Kind::Overdue)]);
+    }
+
+    #[test]
+    fn quiet_hours_wait() {
+        let clock = clock(DAY, 23);
+        let mut scheduler = Scheduler::default();
+        let tasks = [task(0, "today", DAY + 1)];
+
+        assert!(scheduler.check(&clock, &config(), &tasks).is_empty());
+        clock.day.set(DAY + 1);
+        clock.hour.set(7);
+        assert!(scheduler.check(&clock, &config(), &tasks).is_empty());
+        clock.hour.set(8);
+        assert_eq!(kinds(&scheduler.check(&clock, &config(), &tasks)), [("today", Kind::DueSoon)]);
+    }
+
+    #[test]
+    fn quiet_hours_in_the_day() {
+        let config = Config { lead_days : 0, quiet_start : 12, quiet_end : 14 };
+        assert!(!config.is_quiet(11));
+        assert!(config.is_quiet(12));
+        assert!(config.is_quiet(13));
+        assert!(!config.is_quiet(14));
+
+        let none = Config { lead_days : 0, quiet_start : 0, quiet_end : 0 };
+        assert!((0..24).all(|hour| !none.is_quiet(hour)));
+    }
+
+    #[test]
+    fn done_and_moved_tasks() {
+        let clock = clock(DAY, 12);
+        let mut scheduler = Scheduler::default();
+        let done = TaskInfo { done : true, ..task(0, "done", DAY) };
+        assert!(scheduler.check(&clock, &config(), &[done]).is_empty());
+
+        assert_eq!(scheduler.check(&clock, &config(), &[task(1, "moved", DAY)]).len(), 1);
+        // Snoozed to tomorrow, which reminds again once tomorrow comes
+        let moved = [task(1, "moved", DAY + 1)];
+        assert!(scheduler.check(&clock, &config(), &moved).is_empty());
+        clock.day.set(DAY + 1);
+        assert_eq!(kinds(&scheduler.check(&clock, &config(), &moved)), [("moved", Kind::DueSoon)]);
+    }
+}
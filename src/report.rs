@@ -0,0 +1,304 @@
+//! Status reports of the tasks, grouped by category and sub category, as Markdown or a standalone HTML page
+
+use crate::dates;
+use crate::priority::priority_class;
+use crate::undo::TaskData;
+use gtk::prelude::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    /// A checklist, `- [ ]` and `- [x]`
+    Markdown,
+    /// A page with its own styling, nothing to fetch
+    Html,
+}
+impl Format {
+    /// Also the id of its item in the format combo box
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Format::Markdown => "markdown",
+            Format::Html => "html",
+        }
+    }
+
+    pub fn parse(s : &str) -> Option<Format> {
+        match s {
+            "markdown" => Some(Format::Markdown),
+            "html" => Some(Format::Html),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+        }
+    }
+}
+
+/// Where the report goes
+#[derive(Clone, Copy, PartialEq)]
+pub enum Output {
+    File,
+    Clipboard,
+}
+
+/// What `ask` got out of the user
+pub struct Choice {
+    pub format : Format,
+    /// Only the tasks the filter lets through
+    pub filtered : bool,
+    pub output : Output,
+}
+
+/// Asks what kind of report to make and where it goes, `None` if cancelled
+pub fn ask(parent : &gtk::Window) -> Option<Choice> {
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Export Report"),
+        Some(parent),
+        gtk::DialogFlags::MODAL,
+        &[
+            ("Cancel", gtk::ResponseType::Cancel),
+            ("Copy to Clipboard", gtk::ResponseType::Apply),
+            ("Save...", gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_response(gtk::ResponseType::Accept);
+    let content = dialog.get_content_area();
+    content.set_spacing(6);
+
+    let format = gtk::ComboBoxText::new();
+    format.append(Some(Format::Markdown.as_str()), "Markdown checklist");
+    format.append(Some(Format::Html.as_str()), "HTML page");
+    format.set_active_id(Some(Format::Markdown.as_str()));
+    let row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    row.pack_start(&gtk::Label::new(Some("Format")), false, false, 0);
+    row.pack_start(&format, true, true, 0);
+    content.add(&row);
+    let filtered = gtk::CheckButton::with_label("Only the tasks the filter shows");
+    filtered.set_active(true);
+    content.add(&filtered);
+    dialog.show_all();
+
+    let response = dialog.run();
+    let choice = Choice {
+        format : format.get_active_id().and_then(|id| Format::parse(&id)).unwrap_or(Format::Markdown),
+        filtered : filtered.get_active(),
+        output : if response == gtk::ResponseType::Apply { Output::Clipboard } else { Output::File },
+    };
+    dialog.close();
+    match response {
+        gtk::ResponseType::Accept | gtk::ResponseType::Apply => Some(choice),
+        _ => None,
+    }
+}
+
+/// Tasks of a category by sub category
+type SubGroups<'a> = Vec<(&'a str, Vec<&'a TaskData>)>;
+
+/// `tasks` grouped by category then sub category, both in the order they first show up
+fn groups(tasks : &[TaskData]) -> Vec<(&str, SubGroups<'_>)> {
+    let mut groups : Vec<(&str, SubGroups)> = Vec::new();
+    for task in tasks.iter() {
+        let i = match groups.iter().position(|(category, _)| *category == task.category) {
+            Some(i) => i,
+            None => {
+                groups.push((&task.category, Vec::new()));
+                groups.len() - 1
+            }
+        };
+        let subs = &mut groups[i].1;
+        match subs.iter_mut().find(|(sub, _)| *sub == task.sub_category) {
+            Some((_, tasks)) => tasks.push(task),
+            None => subs.push((&task.sub_category, vec![task])),
+        }
+    }
+    // Tasks without a sub category go right under the category
+    for (_, subs) in groups.iter_mut() {
+        subs.sort_by_key(|(sub, _)| !sub.is_empty());
+    }
+    groups
+}
+
+/// Due date and priority, `None` if the task has neither
+fn details(task : &TaskData) -> Option<String> {
+    let mut details = Vec::new();
+    if task.due.is_some() {
+        details.push(format!("due {}", dates::format_due(&task.due)));
+    }
+    if task.priority > 0 {
+        details.push(format!("priority {}", task.priority));
+    }
+    if details.is_empty() { None } else { Some(details.join(", ")) }
+}
+
+/// `tasks` in `format` under the heading `title`, tasks due before `today`(days since the epoch) count as overdue
+pub fn render(format : Format, title : &str, tasks : &[TaskData], today : i64) -> String {
+    match format {
+        Format::Markdown => markdown(title, tasks),
+        Format::Html => html(title, tasks, today),
+    }
+}
+
+/// `text` on one line, with whatever Markdown would make something of escaped
+fn escape_markdown(text : &str) -> String {
+    let mut escaped = String::new();
+    for (i, word) in text.split_whitespace().enumerate() {
+        if i > 0 {
+            escaped.push(' ');
+        }
+        for c in word.chars() {
+            if "\\`*_[]<>#|~&".contains(c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+fn markdown(title : &str, tasks : &[TaskData]) -> String {
+    let mut md = format!("# {}\n", escape_markdown(title));
+    for (category, subs) in groups(tasks).iter() {
+        md.push_str(&format!("\n## {}\n", escape_markdown(category)));
+        for (sub, tasks) in subs.iter() {
+            if !sub.is_empty() {
+                md.push_str(&format!("\n### {}\n", escape_markdown(sub)));
+            }
+            md.push('\n');
+            for task in tasks.iter() {
+                let check = if task.done { "x" } else { " " };
+                md.push_str(&format!("- [{}] {}", check, escape_markdown(&task.name)));
+                if let Some(details) = details(task) {
+                    md.push_str(&format!(" _({})_", details));
+                }
+                md.push('\n');
+            }
+        }
+    }
+    md
+}
+
+fn escape_html(text : &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_STYLE : &str = "\
+body { font-family: sans-serif; max-width: 50em; margin: 2em auto; color: #222; }
+h2 { border-bottom: 1px solid #ccc; }
+ul { list-style: none; padding-left: 0; }
+li { border-left: 4px solid transparent; padding: 2px 6px; margin: 2px 0; }
+li.done { color: #888; text-decoration: line-through; }
+li.overdue .details { color: #c01c28; font-weight: bold; }
+li.priority-high { border-left-color: #c01c28; }
+li.priority-medium { border-left-color: #e5a50a; }
+li.priority-low { border-left-color: #2ec27e; }
+.details { color: #666; font-size: smaller; }
+";
+
+fn html(title : &str, tasks : &[TaskData], today : i64) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n<style>\n{1}</style>\n</head>\n<body>\n<h1>{0}</h1>\n",
+        escape_html(title), HTML_STYLE,
+    );
+    for (category, subs) in groups(tasks).iter() {
+        html.push_str(&format!("<h2>{}</h2>\n", escape_html(category)));
+        for (sub, tasks) in subs.iter() {
+            if !sub.is_empty() {
+                html.push_str(&format!("<h3>{}</h3>\n", escape_html(sub)));
+            }
+            html.push_str("<ul>\n");
+            for task in tasks.iter() {
+                let mut classes = vec![priority_class(task.priority as u32)];
+                if task.done {
+                    classes.push("done");
+                }
+                else if dates::due_days(&task.due) < today {
+                    classes.push("overdue");
+                }
+                let check = if task.done { "&#x2611;" } else { "&#x2610;" };
+                html.push_str(&format!("<li class=\"{}\">{} {}", classes.join(" "), check, escape_html(task.name.trim())));
+                if let Some(details) = details(task) {
+                    html.push_str(&format!(" <span class=\"details\">({})</span>", details));
+                }
+                html.push_str("</li>\n");
+            }
+            html.push_str("</ul>\n");
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cool_organizer::*;
+
+    fn task(name : &str, category : &str, sub_category : &str) -> TaskData {
+        let mut task = TaskData::from_task(&Task::new(name));
+        task.category = category.to_string();
+        task.sub_category = sub_category.to_string();
+        task.due = None;
+        task.done = false;
+        task.priority = 0;
+        task
+    }
+
+    fn tasks() -> Vec<TaskData> {
+        let mut a = task("Fix *the* [bug](http://x)", "work", "back_end");
+        a.priority = 7;
+        a.due = dates::date_from_days(dates::days_from_civil(2024, 1, 5));
+        let mut b = task("Buy milk", "home", "");
+        b.done = true;
+        vec![a, b, task("C# <script>", "work", ""), task("d", "work", "back_end")]
+    }
+
+    #[test]
+    fn grouping() {
+        let tasks = tasks();
+        let groups = groups(&tasks);
+        let names : Vec<(&str, Vec<(&str, usize)>)> = groups.iter()
+            .map(|(category, subs)| (*category, subs.iter().map(|(sub, tasks)| (*sub, tasks.len())).collect()))
+            .collect();
+        assert_eq!(names, [("work", vec![("", 1), ("back_end", 2)]), ("home", vec![("", 1)])]);
+    }
+
+    #[test]
+    fn markdown_checklist() {
+        assert_eq!(render(Format::Markdown, "Tasks", &tasks(), dates::days_from_civil(2024, 1, 6)), "\
+# Tasks
+
+## work
+
+- [ ] C\\# \\<script\\>
+
+### back\\_end
+
+- [ ] Fix \\*the\\* \\[bug\\](http://x) _(due 2024-01-05, priority 7)_
+- [ ] d
+
+## home
+
+- [x] Buy milk
+");
+    }
+
+    #[test]
+    fn html_page() {
+        let html = render(Format::Html, "A & B", &tasks(), dates::days_from_civil(2024, 1, 6));
+        assert!(html.contains("<title>A &amp; B</title>"));
+        assert!(html.contains("<h3>back_end</h3>"));
+        assert!(html.contains("C# &lt;script&gt;"));
+        assert!(html.contains("<li class=\"priority-high overdue\">"));
+        assert!(html.contains("<li class=\"priority-low done\">&#x2611; Buy milk</li>"));
+
+        // Due today isn't overdue yet
+        let html = render(Format::Html, "A & B", &tasks(), dates::days_from_civil(2024, 1, 5));
+        assert!(html.contains("<li class=\"priority-high\">"));
+    }
+}
@@ -0,0 +1,170 @@
+//! The Restore from Backup dialog, previewing what a backup of the task file holds
+
+use crate::dates;
+use crate::storage::{self, Backup};
+use crate::undo::TaskData;
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Columns of the preview
+const COL_NAME : u32 = 0;
+const COL_DUE : u32 = 1;
+const COL_PRIORITY : u32 = 2;
+const COL_DONE : u32 = 3;
+/// Index in the backup's tasks, -1 for category and sub category rows
+const COL_INDEX : u32 = 4;
+
+/// What to restore
+pub enum Restore {
+    /// The backup's tasks instead of the current ones
+    All(Vec<TaskData>),
+    /// Just the backup's tasks at `indices`, on top of the current tasks
+    Selected { backup : Vec<TaskData>, indices : Vec<usize> },
+}
+
+/// Fills `store` with `tasks`, grouped by category and sub category the way the tasks list does
+fn fill(store : &gtk::TreeStore, tasks : &[TaskData]) {
+    store.clear();
+    let mut groups : Vec<(String, gtk::TreeIter)> = Vec::new();
+    for (index, task) in tasks.iter().enumerate() {
+        let mut parent = None;
+        let mut key = String::new();
+        for name in [&task.category, &task.sub_category].iter().filter(|name| !name.is_empty()) {
+            key.push_str(name);
+            key.push('\u{1f}');
+            let iter = match groups.iter().find(|(k, _)| *k == key) {
+                Some((_, iter)) => iter.clone(),
+                None => {
+                    let iter = store.insert_with_values(parent.as_ref(), None, &[COL_NAME, COL_INDEX], &[*name, &-1i64]);
+                    groups.push((key.clone(), iter.clone()));
+                    iter
+                }
+            };
+            parent = Some(iter);
+        }
+        let _ = store.insert_with_values(
+            parent.as_ref(),
+            None,
+            &[COL_NAME, COL_DUE, COL_PRIORITY, COL_DONE, COL_INDEX],
+            &[&task.name, &dates::format_due(&task.due), &task.priority.to_string(), &if task.done { "✔" } else { "" }, &(index as i64)],
+        );
+    }
+}
+
+/// Indices of the task rows at and under `iter`
+fn collect_indices(model : &gtk::TreeModel, iter : &gtk::TreeIter, indices : &mut Vec<usize>) {
+    let index = model.get_value(iter, COL_INDEX as i32).get_some::<i64>().unwrap_or(-1);
+    if index >= 0 && !indices.contains(&(index as usize)) {
+        indices.push(index as usize);
+    }
+    if let Some(child) = model.iter_children(Some(iter)) {
+        loop {
+            collect_indices(model, &child, indices);
+            if !model.iter_next(&child) {
+                break;
+            }
+        }
+    }
+}
+
+/// Asks which backup to restore from and whether to restore all or the selected tasks, `None` if cancelled.
+/// `backups` are newest first, the way `storage::backups` gives them
+pub fn ask_restore(parent : &gtk::Window, backups : &[Backup]) -> Option<Restore> {
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Restore from Backup"),
+        Some(parent),
+        gtk::DialogFlags::MODAL,
+        &[
+            ("Cancel", gtk::ResponseType::Cancel),
+            ("Restore Selected", gtk::ResponseType::Apply),
+            ("Restore All", gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_size(500, 450);
+    let content = dialog.get_content_area();
+    content.set_spacing(6);
+
+    let combo = gtk::ComboBoxText::new();
+    for (i, backup) in backups.iter().enumerate() {
+        combo.append(Some(&i.to_string()), &backup.label());
+    }
+    let row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    row.pack_start(&gtk::Label::new(Some("Backup from")), false, false, 0);
+    row.pack_start(&combo, true, true, 0);
+    content.add(&row);
+
+    // Read-only, none of the cells are editable
+    let store = gtk::TreeStore::new(&[glib::Type::String, glib::Type::String, glib::Type::String, glib::Type::String, glib::Type::I64]);
+    let preview = gtk::TreeView::with_model(&store);
+    for (title, col) in [("Name", COL_NAME), ("Due", COL_DUE), ("Priority", COL_PRIORITY), ("Done", COL_DONE)].iter() {
+        let renderer = gtk::CellRendererText::new();
+        let column = gtk::TreeViewColumn::new();
+        column.set_title(title);
+        column.pack_start(&renderer, true);
+        column.add_attribute(&renderer, "text", *col as i32);
+        preview.append_column(&column);
+    }
+    let selection = preview.get_selection();
+    selection.set_mode(gtk::SelectionMode::Multiple);
+
+    let scroll = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scroll.set_vexpand(true);
+    scroll.add(&preview);
+    content.add(&scroll);
+    let status = gtk::Label::new(None);
+    status.set_halign(gtk::Align::Start);
+    content.add(&status);
+
+    // The tasks of the backup being previewed, empty if it couldn't be loaded
+    let tasks : Rc<RefCell<Vec<TaskData>>> = Rc::new(RefCell::new(Vec::new()));
+    let paths : Vec<String> = backups.iter().map(|backup| backup.path.clone()).collect();
+    let (t, s, st, d) = (Rc::clone(&tasks), store.clone(), status.clone(), dialog.clone());
+    combo.connect_changed(move |combo| {
+        let path = combo.get_active_id().and_then(|id| id.parse::<usize>().ok()).and_then(|i| paths.get(i));
+        let loaded = match path.map(|path| storage::load(path)) {
+            Some(Ok(loaded)) => {
+                st.set_text(&format!("{} tasks", loaded.tasks.len()));
+                loaded.tasks.iter().map(TaskData::from_task).collect()
+            }
+            Some(Err(e)) => {
+                st.set_text(&format!("This backup can't be restored, {}", e));
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        fill(&s, &loaded);
+        d.set_response_sensitive(gtk::ResponseType::Accept, !loaded.is_empty());
+        *t.borrow_mut() = loaded;
+    });
+    let d = dialog.clone();
+    selection.connect_changed(move |selection| {
+        d.set_response_sensitive(gtk::ResponseType::Apply, selection.count_selected_rows() > 0);
+    });
+
+    dialog.show_all();
+    dialog.set_response_sensitive(gtk::ResponseType::Apply, false);
+    combo.set_active(Some(0));
+    preview.expand_all();
+    combo.connect_changed(move |_| preview.expand_all());
+
+    let response = dialog.run();
+    let mut indices = Vec::new();
+    let (rows, model) = selection.get_selected_rows();
+    for path in rows.iter() {
+        if let Some(iter) = model.get_iter(path) {
+            collect_indices(&model, &iter, &mut indices);
+        }
+    }
+    dialog.close();
+
+    let tasks = tasks.borrow();
+    match response {
+        gtk::ResponseType::Accept => Some(Restore::All(tasks.clone())),
+        gtk::ResponseType::Apply => {
+            indices.sort_unstable();
+            Some(Restore::Selected { backup : tasks.clone(), indices })
+        }
+        _ => None,
+    }
+}
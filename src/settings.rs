@@ -0,0 +1,241 @@
+//! Ui settings which are remembered between runs, kept in a `glib::KeyFile`
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Everything but the shortcuts
+const GROUP : &str = "ui";
+/// Action name to accelerator, see `shortcuts::SHORTCUTS`
+const SHORTCUTS_GROUP : &str = "shortcuts";
+
+/// When edits in the task grid get saved without pressing Save
+#[derive(Clone, Copy, PartialEq)]
+pub enum Autosave {
+    /// Only Save saves, anything else asks first
+    Off,
+    /// Shortly after a field stops changing
+    FieldChange,
+    /// When another task gets selected
+    SelectionChange,
+    /// Every `Settings::autosave_interval` seconds
+    Timed,
+}
+impl Autosave {
+    fn as_str(self) -> &'static str {
+        match self {
+            Autosave::Off => "off",
+            Autosave::FieldChange => "field",
+            Autosave::SelectionChange => "selection",
+            Autosave::Timed => "timed",
+        }
+    }
+
+    fn parse(s : &str) -> Option<Autosave> {
+        match s {
+            "off" => Some(Autosave::Off),
+            "field" => Some(Autosave::FieldChange),
+            "selection" => Some(Autosave::SelectionChange),
+            "timed" => Some(Autosave::Timed),
+            _ => None,
+        }
+    }
+}
+
+/// What the tasks list groups tasks by
+#[derive(Clone, Copy, PartialEq)]
+pub enum Grouping {
+    /// Category, then sub category
+    Category,
+    /// Due date, see `agenda::Bucket`
+    Agenda,
+}
+impl Grouping {
+    /// Also the id of its item in the grouping combo box
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Grouping::Category => "category",
+            Grouping::Agenda => "agenda",
+        }
+    }
+
+    pub fn parse(s : &str) -> Option<Grouping> {
+        match s {
+            "category" => Some(Grouping::Category),
+            "agenda" => Some(Grouping::Agenda),
+            _ => None,
+        }
+    }
+}
+
+/// What the columns of the board are
+#[derive(Clone, Copy, PartialEq)]
+pub enum BoardColumns {
+    Category,
+    Priority,
+}
+impl BoardColumns {
+    /// Also the id of its item in the board's combo box
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BoardColumns::Category => "category",
+            BoardColumns::Priority => "priority",
+        }
+    }
+
+    pub fn parse(s : &str) -> Option<BoardColumns> {
+        match s {
+            "category" => Some(BoardColumns::Category),
+            "priority" => Some(BoardColumns::Priority),
+            _ => None,
+        }
+    }
+}
+
+pub struct Settings {
+    /// Model column the tasks list is sorted by, `None` when it is unsorted
+    pub sort_column : Option<u32>,
+    pub sort_ascending : bool,
+    pub grouping : Grouping,
+    pub board_columns : BoardColumns,
+    pub autosave : Autosave,
+    /// Seconds between saves for `Autosave::Timed`, only read at startup
+    pub autosave_interval : u32,
+    /// Desktop notifications for tasks due soon or just overdue
+    pub reminders : bool,
+    /// Days ahead of the due date reminders start, 0 is on the day itself
+    pub reminder_lead_days : u32,
+    /// Hours(0-23) between which no reminders are shown, the same hour twice turns it off
+    pub quiet_start : u32,
+    pub quiet_end : u32,
+    /// todo.txt file written next to the task file on every save, see `todo_txt`
+    pub todo_txt_mirror : Option<String>,
+    /// Category of new tasks when nothing is selected, `None` leaves it to `Task::new`
+    pub default_category : Option<String>,
+    /// Backups of the task file kept at most, 0 makes none, see `storage::BackupPolicy`
+    pub backup_count : u32,
+    /// Days backups are kept for, 0 keeps them until there are too many
+    pub backup_max_age_days : u32,
+    /// Rebound keyboard shortcuts, there is no ui for it so they are only read at startup
+    pub shortcuts : HashMap<String, String>,
+}
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            sort_column : None,
+            sort_ascending : true,
+            grouping : Grouping::Category,
+            board_columns : BoardColumns::Category,
+            autosave : Autosave::Off,
+            autosave_interval : 60,
+            reminders : true,
+            reminder_lead_days : 0,
+            quiet_start : 22,
+            quiet_end : 8,
+            todo_txt_mirror : None,
+            default_category : None,
+            backup_count : 10,
+            backup_max_age_days : 30,
+            shortcuts : HashMap::new(),
+        }
+    }
+}
+impl Settings {
+    /// `$XDG_CONFIG_HOME/corganizerui/settings.ini`
+    pub fn path() -> PathBuf {
+        let mut path = glib::get_user_config_dir().unwrap_or_else(|| PathBuf::from("."));
+        path.push("corganizerui");
+        path.push("settings.ini");
+        path
+    }
+
+    /// Loads the settings, anything missing(or the whole file) falls back to the default
+    pub fn load() -> Settings {
+        let mut settings = Settings::default();
+
+        let file = glib::KeyFile::new();
+        if file.load_from_file(Settings::path(), glib::KeyFileFlags::NONE).is_err() {
+            return settings;
+        }
+
+        if let Ok(col) = file.get_integer(GROUP, "sort_column") {
+            settings.sort_column = if col >= 0 { Some(col as u32) } else { None };
+        }
+        if let Ok(asc) = file.get_boolean(GROUP, "sort_ascending") {
+            settings.sort_ascending = asc;
+        }
+        if let Some(grouping) = file.get_string(GROUP, "grouping").ok().and_then(|s| Grouping::parse(&s)) {
+            settings.grouping = grouping;
+        }
+        if let Some(columns) = file.get_string(GROUP, "board_columns").ok().and_then(|s| BoardColumns::parse(&s)) {
+            settings.board_columns = columns;
+        }
+        if let Some(autosave) = file.get_string(GROUP, "autosave").ok().and_then(|s| Autosave::parse(&s)) {
+            settings.autosave = autosave;
+        }
+        if let Ok(interval) = file.get_integer(GROUP, "autosave_interval") {
+            // Saving more than once a second is pointless
+            settings.autosave_interval = interval.max(1) as u32;
+        }
+        if let Ok(reminders) = file.get_boolean(GROUP, "reminders") {
+            settings.reminders = reminders;
+        }
+        if let Ok(days) = file.get_integer(GROUP, "reminder_lead_days") {
+            settings.reminder_lead_days = days.max(0) as u32;
+        }
+        if let Ok(hour) = file.get_integer(GROUP, "quiet_start") {
+            settings.quiet_start = hour.clamp(0, 23) as u32;
+        }
+        if let Ok(hour) = file.get_integer(GROUP, "quiet_end") {
+            settings.quiet_end = hour.clamp(0, 23) as u32;
+        }
+        if let Ok(path) = file.get_string(GROUP, "todo_txt_mirror") {
+            settings.todo_txt_mirror = Some(path.to_string()).filter(|path| !path.is_empty());
+        }
+        if let Ok(category) = file.get_string(GROUP, "default_category") {
+            settings.default_category = Some(category.trim().to_string()).filter(|category| !category.is_empty());
+        }
+        if let Ok(count) = file.get_integer(GROUP, "backup_count") {
+            settings.backup_count = count.max(0) as u32;
+        }
+        if let Ok(days) = file.get_integer(GROUP, "backup_max_age_days") {
+            settings.backup_max_age_days = days.max(0) as u32;
+        }
+        if let Ok((actions, _)) = file.get_keys(SHORTCUTS_GROUP) {
+            for action in actions.iter() {
+                if let Ok(accel) = file.get_string(SHORTCUTS_GROUP, action) {
+                    settings.shortcuts.insert(action.to_string(), accel.to_string());
+                }
+            }
+        }
+
+        settings
+    }
+
+    pub fn save(&self) -> Result<(), glib::Error> {
+        let file = glib::KeyFile::new();
+
+        file.set_integer(GROUP, "sort_column", self.sort_column.map(|c| c as i32).unwrap_or(-1));
+        file.set_boolean(GROUP, "sort_ascending", self.sort_ascending);
+        file.set_string(GROUP, "grouping", self.grouping.as_str());
+        file.set_string(GROUP, "board_columns", self.board_columns.as_str());
+        file.set_string(GROUP, "autosave", self.autosave.as_str());
+        file.set_integer(GROUP, "autosave_interval", self.autosave_interval as i32);
+        file.set_boolean(GROUP, "reminders", self.reminders);
+        file.set_integer(GROUP, "reminder_lead_days", self.reminder_lead_days as i32);
+        file.set_integer(GROUP, "quiet_start", self.quiet_start as i32);
+        file.set_integer(GROUP, "quiet_end", self.quiet_end as i32);
+        file.set_string(GROUP, "todo_txt_mirror", self.todo_txt_mirror.as_deref().unwrap_or(""));
+        file.set_string(GROUP, "default_category", self.default_category.as_deref().unwrap_or(""));
+        file.set_integer(GROUP, "backup_count", self.backup_count as i32);
+        file.set_integer(GROUP, "backup_max_age_days", self.backup_max_age_days as i32);
+        for (action, accel) in self.shortcuts.iter() {
+            file.set_string(SHORTCUTS_GROUP, action, accel);
+        }
+
+        let path = Settings::path();
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        file.save_to_file(path)
+    }
+}
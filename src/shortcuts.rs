@@ -0,0 +1,86 @@
+//! Keyboard shortcuts, each one activates an action of the application(`app.<action>`)
+
+use std::collections::HashMap;
+
+pub struct Shortcut {
+    /// Name of the action, also the key to rebind it under in the settings
+    pub action : &'static str,
+    /// What the shortcuts window calls it
+    pub title : &'static str,
+    /// Group it is listed under in the shortcuts window
+    pub group : &'static str,
+    /// Accelerator unless the settings say otherwise, in `gtk::accelerator_parse` format
+    pub accel : &'static str,
+}
+
+/// Every shortcut, in the order the shortcuts window lists them.
+/// Ones without modifiers only work while the tasks list has the focus
+pub const SHORTCUTS : &[Shortcut] = &[
+    Shortcut { action : "new", title : "New task", group : "Tasks", accel : "<Control>n" },
+    Shortcut { action : "save", title : "Save changes", group : "Tasks", accel : "<Control>s" },
+    Shortcut { action : "delete", title : "Delete the selected tasks", group : "Tasks", accel : "Delete" },
+    Shortcut { action : "toggle-done", title : "Toggle done", group : "Tasks", accel : "space" },
+    Shortcut { action : "raise-priority", title : "Raise priority", group : "Tasks", accel : "plus" },
+    Shortcut { action : "lower-priority", title : "Lower priority", group : "Tasks", accel : "minus" },
+    Shortcut { action : "clear-due", title : "Clear the due date", group : "Tasks", accel : "<Control>d" },
+    Shortcut { action : "quick-add", title : "Quick add a task", group : "Tasks", accel : "<Control>l" },
+    Shortcut { action : "search", title : "Search", group : "General", accel : "<Control>f" },
+    Shortcut { action : "undo", title : "Undo", group : "General", accel : "<Control>z" },
+    Shortcut { action : "redo", title : "Redo", group : "General", accel : "<Control><Shift>z" },
+    Shortcut { action : "shortcuts", title : "Keyboard shortcuts", group : "General", accel : "<Control>question" },
+];
+
+/// The accelerator of every shortcut, `overrides`(from the settings) win over the defaults.
+/// An empty accelerator leaves the shortcut unbound
+pub fn bindings(overrides : &HashMap<String, String>) -> Vec<(&'static Shortcut, String)> {
+    SHORTCUTS.iter()
+        .map(|shortcut| {
+            let accel = overrides.get(shortcut.action)
+                .cloned()
+                .unwrap_or_else(|| shortcut.accel.to_string());
+            (shortcut, accel)
+        })
+        .collect()
+}
+
+/// Builder xml of a `GtkShortcutsWindow`(id `shortcuts_window`) listing `bindings`
+pub fn window_xml(bindings : &[(&Shortcut, String)]) -> String {
+    let mut groups : Vec<&str> = Vec::new();
+    for (shortcut, _) in bindings.iter() {
+        if !groups.contains(&shortcut.group) {
+            groups.push(shortcut.group);
+        }
+    }
+
+    let mut xml = String::from(concat!(
+        "<interface>\n",
+        "  <object class=\"GtkShortcutsWindow\" id=\"shortcuts_window\">\n",
+        "    <property name=\"modal\">True</property>\n",
+        "    <child>\n",
+        "      <object class=\"GtkShortcutsSection\">\n",
+        "        <property name=\"visible\">True</property>\n",
+        "        <property name=\"section-name\">main</property>\n",
+    ));
+    for group in groups {
+        xml.push_str(&format!(concat!(
+            "        <child>\n",
+            "          <object class=\"GtkShortcutsGroup\">\n",
+            "            <property name=\"visible\">True</property>\n",
+            "            <property name=\"title\">{}</property>\n",
+        ), glib::markup_escape_text(group)));
+        for (shortcut, accel) in bindings.iter().filter(|(s, accel)| s.group == group && !accel.is_empty()) {
+            xml.push_str(&format!(concat!(
+                "            <child>\n",
+                "              <object class=\"GtkShortcutsShortcut\">\n",
+                "                <property name=\"visible\">True</property>\n",
+                "                <property name=\"title\">{}</property>\n",
+                "                <property name=\"accelerator\">{}</property>\n",
+                "              </object>\n",
+                "            </child>\n",
+            ), glib::markup_escape_text(shortcut.title), glib::markup_escape_text(accel)));
+        }
+        xml.push_str("          </object>\n        </child>\n");
+    }
+    xml.push_str("      </object>\n    </child>\n  </object>\n</interface>\n");
+    xml
+}
@@ -0,0 +1,306 @@
+//! Loading and saving the task file without silently losing anything
+
+use cool_organizer::*;
+use crate::dates;
+use crate::undo::TaskData;
+use std::fmt;
+use std::path::Path;
+
+/// The file the tasks are kept in, and whether it is up to date
+#[derive(Default)]
+pub struct TaskFile {
+    /// `None` when there is nowhere safe to save to(the file failed to load and we started empty)
+    pub path : Option<String>,
+    /// There are changes which didn't make it to the disk yet
+    pub dirty : bool,
+    /// What the file held the last time we loaded or saved it, so our own saves
+    /// aren't mistaken for someone else changing the file
+    pub known_content : Option<String>,
+    /// The tasks as of `known_content`, what both sides started from when merging
+    pub base : Vec<TaskData>,
+}
+impl TaskFile {
+    /// Marks `tasks` as being what is on the disk right now
+    pub fn synced(&mut self, tasks : &TasksManager) {
+        self.dirty = false;
+        self.known_content = self.path.as_ref().and_then(|path| std::fs::read_to_string(path).ok());
+        self.base = tasks.tasks.iter().map(TaskData::from_task).collect();
+    }
+
+    /// Name of the file for the window title
+    pub fn display_name(&self) -> String {
+        match &self.path {
+            Some(path) => Path::new(path).file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone()),
+            None => "Not Saved".to_string(),
+        }
+    }
+}
+
+pub enum LoadError {
+    /// The file is there but couldn't be read
+    Io(std::io::Error),
+    /// The file was read but isn't a task list, with what is wrong with it
+    Parse(String),
+}
+impl fmt::Display for LoadError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "couldn't read the file: {}", e),
+            LoadError::Parse(e) => write!(f, "the file doesn't look like a task list: {}", e),
+        }
+    }
+}
+
+/// Loads the tasks at `path`, a missing or blank file is just an empty task list
+pub fn load(path : &str) -> Result<TasksManager, LoadError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(empty_tasks()),
+        Err(e) => return Err(LoadError::Io(e)),
+    };
+    if content.trim().is_empty() {
+        return Ok(empty_tasks());
+    }
+    // The same toml `TasksManager::load` reads, which panics where this hands back the error
+    toml::from_str(&content).map_err(|e| LoadError::Parse(e.to_string()))
+}
+
+pub fn empty_tasks() -> TasksManager {
+    TasksManager { tasks : Vec::new() }
+}
+
+/// How many backups of the task file to keep around, see `save`
+pub struct BackupPolicy {
+    /// Backups kept at most, 0 turns backing up off(and leaves the old ones alone)
+    pub keep : usize,
+    /// Days after which a backup gets deleted, 0 keeps them however old they are
+    pub max_age_days : u32,
+}
+
+/// A copy of the task file from right before some save, `<task file>.<YYYYMMDD-HHMMSS>.bak`.
+/// Older versions kept a single `<task file>.bak`, which goes by when it was last written
+pub struct Backup {
+    pub path : String,
+    /// `YYYYMMDD-HHMMSS` in local time, with a `-N` after it if there were several in a second
+    stamp : String,
+}
+impl Backup {
+    /// When it was made, `YYYY-MM-DD HH:MM:SS`
+    pub fn label(&self) -> String {
+        let s = &self.stamp;
+        format!("{}-{}-{} {}:{}:{}{}", &s[..4], &s[4..6], &s[6..8], &s[9..11], &s[11..13], &s[13..15], &s[15..])
+    }
+
+    /// Days since the epoch it was made on
+    fn day(&self) -> Option<i64> {
+        let s = &self.stamp;
+        dates::checked_days(s[..4].parse().ok()?, s[4..6].parse().ok()?, s[6..8].parse().ok()?)
+    }
+}
+
+/// Whether `stamp` is `YYYYMMDD-HHMMSS`, maybe followed by `-N`
+fn is_stamp(stamp : &str) -> bool {
+    let digits = |s : &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    match (stamp.get(..8), stamp.get(8..9), stamp.get(9..15), stamp.get(15..)) {
+        (Some(date), Some("-"), Some(time), Some(rest)) => {
+            digits(date) && digits(time) && (rest.is_empty() || rest.strip_prefix('-').map(digits).unwrap_or(false))
+        }
+        _ => false,
+    }
+}
+
+/// The backups of the task file at `path`, newest first
+pub fn backups(path : &str) -> Vec<Backup> {
+    let path = Path::new(path);
+    let (dir, name) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => (dir, format!("{}.", name.to_string_lossy())),
+        _ => return Vec::new(),
+    };
+    // A bare file name has an empty parent
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut backups : Vec<Backup> = entries.filter_map(|entry| {
+        let entry = entry.ok()?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let stamp = match file_name.strip_prefix(&name)? {
+            "bak" => {
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                let seconds = modified.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs();
+                local_stamp(&glib::DateTime::from_unix_local(seconds as i64))
+            }
+            rest => rest.strip_suffix(".bak").filter(|stamp| is_stamp(stamp))?.to_string(),
+        };
+        Some(Backup { path : dir.join(&file_name).to_string_lossy().into_owned(), stamp })
+    }).collect();
+    // The `-N` ones were made after the first one of their second
+    backups.sort_by_key(|backup| {
+        let n = backup.stamp.get(16..).and_then(|n| n.parse::<u32>().ok()).unwrap_or(1);
+        std::cmp::Reverse((backup.stamp[..15].to_string(), n))
+    });
+    backups
+}
+
+/// `time` as `YYYYMMDD-HHMMSS`
+fn local_stamp(time : &glib::DateTime) -> String {
+    let (year, month, day) = time.get_ymd();
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, time.get_hour(), time.get_minute(), time.get_second())
+}
+
+/// Copies the task file at `path` to a new backup, then deletes the backups `policy` doesn't keep
+fn back_up(path : &str, policy : &BackupPolicy) -> Result<(), String> {
+    back_up_at(path, policy, &local_stamp(&glib::DateTime::new_now_local()), dates::today())
+}
+
+/// `back_up` as if it was `stamp`(see `Backup`) on the day `today`
+fn back_up_at(path : &str, policy : &BackupPolicy, stamp : &str, today : i64) -> Result<(), String> {
+    if policy.keep == 0 || !Path::new(path).exists() {
+        return Ok(());
+    }
+    let existing = backups(path);
+    let content = std::fs::read(path).map_err(|e| format!("couldn't read the previous file: {}", e))?;
+    // Nothing changed since the last backup, another copy would only push an older one out
+    let unchanged = existing.first()
+        .and_then(|newest| std::fs::read(&newest.path).ok())
+        .map(|newest| newest == content)
+        .unwrap_or(false);
+
+    if !unchanged {
+        let mut backup = format!("{}.{}.bak", path, stamp);
+        let mut n = 1;
+        while Path::new(&backup).exists() {
+            n += 1;
+            backup = format!("{}.{}-{}.bak", path, stamp, n);
+        }
+        std::fs::write(&backup, &content).map_err(|e| format!("couldn't back up the previous file: {}", e))?;
+    }
+
+    for (i, backup) in backups(path).iter().enumerate() {
+        let too_old = policy.max_age_days > 0
+            && backup.day().map(|day| today - day > policy.max_age_days as i64).unwrap_or(false);
+        // The newest one stays whatever its age, it is what the file was just now
+        if i >= policy.keep || (i > 0 && too_old) {
+            let _ = std::fs::remove_file(&backup.path);
+        }
+    }
+    Ok(())
+}
+
+/// Saves the tasks to `path`, backing up the previous version first as `policy` says
+pub fn save(tasks : &TasksManager, path : &str, policy : &BackupPolicy) -> Result<(), String> {
+    back_up(path, policy)?;
+    tasks.save(&path.to_string()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// An empty directory of its own for a test, gone again when it's dropped
+    struct TempDir(PathBuf);
+    impl TempDir {
+        fn new(name : &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!("corganizerui-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn file(&self, name : &str) -> String {
+            self.0.join(name).to_string_lossy().into_owned()
+        }
+    }
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn stamps(path : &str) -> Vec<String> {
+        backups(path).into_iter().map(|backup| backup.stamp).collect()
+    }
+
+    #[test]
+    fn stamps_are_checked() {
+        assert!(is_stamp("20240131-235959"));
+        assert!(is_stamp("20240131-235959-12"));
+        assert!(!is_stamp("20240131-235959-"));
+        assert!(!is_stamp("20240131_235959"));
+        assert!(!is_stamp("2024013-1235959"));
+        assert!(!is_stamp("20240131-23595"));
+        assert!(!is_stamp("20240131-235959-x"));
+    }
+
+    #[test]
+    fn newest_first() {
+        let dir = TempDir::new("newest-first");
+        let path = dir.file("tasks.toml");
+        for name in ["20240101-120000", "20240101-120000-2", "20240101-120000-10", "20240102-080000"].iter() {
+            std::fs::write(format!("{}.{}.bak", path, name), "").unwrap();
+        }
+        // Not backups of this file
+        std::fs::write(format!("{}.notes.bak", path), "").unwrap();
+        std::fs::write(dir.file("other.toml.20240103-080000.bak"), "").unwrap();
+
+        assert_eq!(stamps(&path), ["20240102-080000", "20240101-120000-10", "20240101-120000-2", "20240101-120000"]);
+        assert_eq!(backups(&path)[1].label(), "2024-01-01 12:00:00-10");
+    }
+
+    #[test]
+    fn old_single_backup() {
+        let dir = TempDir::new("old-single-backup");
+        let path = dir.file("tasks.toml");
+        std::fs::write(format!("{}.20240101-120000.bak", path), "").unwrap();
+        std::fs::write(format!("{}.bak", path), "").unwrap();
+
+        let backups = backups(&path);
+        assert_eq!(backups.len(), 2);
+        // Written just now
+        assert!(backups[0].path.ends_with("tasks.toml.bak"));
+        assert!(is_stamp(&backups[0].stamp));
+    }
+
+    #[test]
+    fn pruning() {
+        let dir = TempDir::new("pruning");
+        let path = dir.file("tasks.toml");
+        let today = dates::days_from_civil(2024, 3, 1);
+        let policy = BackupPolicy { keep : 3, max_age_days : 30 };
+
+        // Nothing to back up yet
+        back_up_at(&path, &policy, "20240301-120000", today).unwrap();
+        assert!(stamps(&path).is_empty());
+
+        std::fs::write(&path, "a").unwrap();
+        std::fs::write(format!("{}.20240101-120000.bak", path), "old").unwrap();
+        std::fs::write(format!("{}.20240220-120000.bak", path), "b").unwrap();
+        back_up_at(&path, &policy, "20240301-120000", today).unwrap();
+        // The one from january is too old
+        assert_eq!(stamps(&path), ["20240301-120000", "20240220-120000"]);
+
+        // Same content as the newest backup, no copy
+        back_up_at(&path, &policy, "20240301-120001", today).unwrap();
+        assert_eq!(stamps(&path).len(), 2);
+
+        std::fs::write(&path, "c").unwrap();
+        back_up_at(&path, &policy, "20240301-120000", today).unwrap();
+        std::fs::write(&path, "d").unwrap();
+        back_up_at(&path, &policy, "20240301-120000", today).unwrap();
+        // Only 3 kept, the ones made in the same second get numbered
+        assert_eq!(stamps(&path), ["20240301-120000-3", "20240301-120000-2", "20240301-120000"]);
+        assert_eq!(std::fs::read_to_string(format!("{}.20240301-120000-2.bak", path)).unwrap(), "c");
+
+        // The newest stays however old it is, and 0 turns backing up off
+        back_up_at(&path, &policy, "20240301-120002", today + 365).unwrap();
+        assert_eq!(stamps(&path), ["20240301-120000-3"]);
+        std::fs::write(&path, "e").unwrap();
+        back_up_at(&path, &BackupPolicy { keep : 0, max_age_days : 0 }, "20240301-120003", today).unwrap();
+        assert_eq!(stamps(&path), ["20240301-120000-3"]);
+    }
+}
@@ -0,0 +1,370 @@
+//! Tasks in the todo.txt format(<https://github.com/todotxt/todo.txt>), a task per line
+
+use crate::dates;
+use crate::merge::{self, Import};
+use crate::priority::MAX_PRIORITY;
+use crate::undo::TaskData;
+use cool_organizer::*;
+
+/// `(A)` is the highest priority, `MAX_PRIORITY` for us. Priority 0 has no letter
+fn priority_letter(priority : u8) -> Option<char> {
+    if priority == 0 {
+        None
+    }
+    else {
+        Some((b'A' + MAX_PRIORITY - priority.min(MAX_PRIORITY)) as char)
+    }
+}
+
+/// Letters past the ones we have all become priority 1
+fn letter_priority(letter : char) -> Option<u8> {
+    if !letter.is_ascii_uppercase() {
+        return None;
+    }
+    let below_top = letter as u8 - b'A';
+    Some(MAX_PRIORITY.saturating_sub(below_top).max(1))
+}
+
+/// Whether `%` at the start of `text` would be read as an escape
+fn is_escape(text : &str) -> bool {
+    let hex = text.as_bytes().get(1..3).unwrap_or(&[]);
+    text.starts_with('%') && hex.len() == 2 && hex.iter().all(u8::is_ascii_hexdigit)
+}
+
+/// `%XX` of an ASCII character
+fn escape_char(c : char) -> String {
+    format!("%{:02X}", c as u32)
+}
+
+/// Escapes whatever `must` says has to be, and any `%` which would be read as an escape.
+/// todo.txt has no escapes of its own, so this goes the way URLs do
+fn escape(text : &str, must : impl Fn(usize, char) -> bool) -> String {
+    let mut escaped = String::new();
+    for (i, c) in text.char_indices() {
+        if must(i, c) || (c == '%' && is_escape(&text[i..])) {
+            escaped.push_str(&escape_char(c));
+        }
+        else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Undoes `escape`
+fn unescape(text : &str) -> String {
+    let mut unescaped = String::new();
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        match u8::from_str_radix(text.get(i + 1..i + 3).unwrap_or(""), 16) {
+            Ok(byte) if is_escape(&text[i..]) && byte.is_ascii() => {
+                unescaped.push(byte as char);
+                i += 3;
+            }
+            _ => {
+                unescaped.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    unescaped
+}
+
+/// `+project` and `@context` end at the first space, so spaces are escaped
+fn tag(text : &str) -> String {
+    escape(text, |_, c| c.is_whitespace())
+}
+
+/// Whether `word` is a priority, `(A)`
+fn is_priority(word : &str) -> bool {
+    let mut chars = word.chars();
+    chars.next() == Some('(') && chars.next().map(|c| c.is_ascii_uppercase()).unwrap_or(false)
+        && chars.next() == Some(')') && chars.next().is_none()
+}
+
+/// A word of a name, escaped where it would be read as something else.
+/// The first word can't look like the `x` of done tasks, a priority or a date either
+fn name_word(word : &str, first : bool) -> String {
+    let tag = word.len() > 1 && word.starts_with(['+', '@']);
+    let key = word.starts_with("due:") || word.starts_with("pri:");
+    let start = first && (word == "x" || is_priority(word) || dates::parse_iso(word).is_some());
+    escape(word, |i, c| (i == 0 && (tag || start)) || (key && c == ':' && i == 3))
+}
+
+/// The todo.txt line of `task`. Done tasks keep their priority as `pri:X`, the way todo.txt tools do
+pub fn line(task : &TaskData) -> String {
+    let mut parts = Vec::new();
+    if task.done {
+        parts.push("x".to_string());
+    }
+    else if let Some(letter) = priority_letter(task.priority) {
+        parts.push(format!("({})", letter));
+    }
+    // A name on several lines would be several tasks
+    let name : Vec<String> = task.name.split_whitespace().enumerate().map(|(i, word)| name_word(word, i == 0)).collect();
+    parts.push(name.join(" "));
+    if !task.category.is_empty() {
+        parts.push(format!("+{}", tag(&task.category)));
+    }
+    if !task.sub_category.is_empty() {
+        parts.push(format!("@{}", tag(&task.sub_category)));
+    }
+    if task.due.is_some() {
+        parts.push(format!("due:{}", dates::format_due(&task.due)));
+    }
+    if let (true, Some(letter)) = (task.done, priority_letter(task.priority)) {
+        parts.push(format!("pri:{}", letter));
+    }
+    parts.join(" ")
+}
+
+/// `tasks` as a todo.txt file
+pub fn write(tasks : &[TaskData]) -> String {
+    tasks.iter().map(|task| line(task) + "\n").collect()
+}
+
+/// The task on a todo.txt line, `None` for blank lines.
+/// Only the first `+project` and `@context` are taken, any others stay in the name
+pub fn parse_line(line : &str) -> Option<TaskData> {
+    let mut words = line.split_whitespace().peekable();
+    words.peek()?;
+    let mut task = TaskData::from_task(&Task::new(""));
+
+    if words.peek() == Some(&"x") {
+        task.done = true;
+        words.next();
+    }
+    else if let Some(letter) = words.peek().and_then(|w| w.strip_prefix('(')).and_then(|w| w.strip_suffix(')')) {
+        let mut chars = letter.chars();
+        if let (Some(letter), None) = (chars.next(), chars.next()) {
+            if let Some(priority) = letter_priority(letter) {
+                task.priority = priority;
+                words.next();
+            }
+        }
+    }
+    // Completion and creation dates, which tasks don't have
+    while words.peek().map(|w| dates::parse_iso(w).is_some()).unwrap_or(false) {
+        words.next();
+    }
+
+    let mut name = Vec::new();
+    let (mut category, mut sub_category) = (None, None);
+    for word in words {
+        if let (Some(project), None) = (word.strip_prefix('+').filter(|p| !p.is_empty()), &category) {
+            category = Some(unescape(project));
+        }
+        else if let (Some(context), None) = (word.strip_prefix('@').filter(|c| !c.is_empty()), &sub_category) {
+            sub_category = Some(unescape(context));
+        }
+        else if let Some(due) = word.strip_prefix("due:").and_then(dates::parse_iso).and_then(dates::date_from_days) {
+            task.due = Some(due);
+        }
+        else if let Some(priority) = word.strip_prefix("pri:").and_then(|p| p.chars().next()).and_then(letter_priority) {
+            task.priority = priority;
+        }
+        else {
+            name.push(unescape(word));
+        }
+    }
+    if let Some(category) = category {
+        task.category = category;
+    }
+    task.sub_category = sub_category.unwrap_or_default();
+    task.name = name.join(" ");
+    Some(task)
+}
+
+/// Every task in a todo.txt file
+pub fn parse(text : &str) -> Vec<TaskData> {
+    text.lines().filter_map(parse_line).filter(|task| !task.name.is_empty()).collect()
+}
+
+/// Pairs of a line of `old` and one of `new` which are the same, as few lines as possible left out of them
+fn common_lines(old : &[&str], new : &[&str]) -> Vec<(usize, usize)> {
+    // Longest common subsequence, `lengths[i][j]` is its length for `old[i..]` and `new[j..]`
+    let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] { lengths[i + 1][j + 1] + 1 } else { lengths[i + 1][j].max(lengths[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = Vec::new();
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        }
+        else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        }
+        else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// What editing the todo.txt file `write` made of `tasks` into `edited` did to them.
+/// Lines which were changed in place change their task, other lines which are gone remove theirs and new ones are added
+pub fn changes(tasks : &[TaskData], edited : &str) -> Import {
+    let written : Vec<String> = tasks.iter().map(line).collect();
+    let old : Vec<&str> = written.iter().map(|line| line.as_str()).collect();
+    let new : Vec<&str> = edited.lines().map(|line| line.trim_end()).collect();
+    let mut import = Import::default();
+
+    let mut pairs = common_lines(&old, &new);
+    pairs.push((old.len(), new.len()));
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in pairs.into_iter() {
+        // What changed in between, lines of the same name are most likely the same task, then those in the same category
+        let before : Vec<Option<TaskData>> = old[i..next_i].iter().map(|line| parse_line(line)).collect();
+        let mut after : Vec<Option<TaskData>> = new[j..next_j].iter()
+            .map(|line| parse_line(line).filter(|task| !task.name.is_empty()))
+            .collect();
+        let alikes : [fn(&TaskData, &TaskData) -> bool; 3] = [
+            |a, b| a.name.split_whitespace().eq(b.name.split_whitespace()),
+            |a, b| a.category == b.category && a.sub_category == b.sub_category,
+            |_, _| true,
+        ];
+        let mut matched : Vec<Option<TaskData>> = vec![None; before.len()];
+        for alike in alikes.iter() {
+            for (old, m) in before.iter().zip(matched.iter_mut()).filter(|(_, m)| m.is_none()) {
+                let found = after.iter().position(|new| match (old, new) {
+                    (Some(old), Some(new)) => alike(old, new),
+                    (None, Some(_)) => true,
+                    _ => false,
+                });
+                if let Some(n) = found {
+                    *m = after[n].take();
+                }
+            }
+        }
+
+        for ((index, old), m) in (i..next_i).zip(before.iter()).zip(matched) {
+            match m {
+                Some(new) => {
+                    // Only what the line changed, the rest(like spacing the line can't have) stays
+                    let task = match old {
+                        Some(old) => merge::merge_task(old, &new, &tasks[index]),
+                        None => new,
+                    };
+                    if !task.same_as(&tasks[index]) {
+                        import.changed.push((index, task));
+                    }
+                }
+                None => import.removed.push(index),
+            }
+        }
+        import.added.extend(after.into_iter().flatten());
+        i = next_i + 1;
+        j = next_j + 1;
+    }
+    import
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name : &str, category : &str, sub_category : &str) -> TaskData {
+        let mut task = TaskData::from_task(&Task::new(name));
+        task.category = category.to_string();
+        task.sub_category = sub_category.to_string();
+        task.due = None;
+        task.done = false;
+        task.priority = 0;
+        task
+    }
+
+    #[test]
+    fn priorities() {
+        assert_eq!(priority_letter(0), None);
+        assert_eq!(priority_letter(MAX_PRIORITY), Some('A'));
+        assert_eq!(priority_letter(1), Some('H'));
+        assert_eq!(letter_priority('A'), Some(MAX_PRIORITY));
+        assert_eq!(letter_priority('H'), Some(1));
+        assert_eq!(letter_priority('Z'), Some(1));
+        assert_eq!(letter_priority('a'), None);
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(tag("My Project"), "My%20Project");
+        assert_eq!(unescape("My%20Project"), "My Project");
+        // Only a `%` which looks like an escape gets escaped
+        assert_eq!(escape("50% %41", |_, _| false), "50% %2541");
+        assert_eq!(unescape("50% %2541"), "50% %41");
+        assert_eq!(name_word("+1", false), "%2B1");
+        assert_eq!(name_word("due:soon", false), "due%3Asoon");
+        assert_eq!(name_word("x", true), "%78");
+        assert_eq!(name_word("x", false), "x");
+        assert_eq!(name_word("(A)", true), "%28A)");
+        assert_eq!(name_word("2024-01-01", true), "%32024-01-01");
+        assert_eq!(name_word("+", false), "+");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut a = task("Call mom", "My Family", "on the phone");
+        a.priority = 8;
+        a.due = dates::date_from_days(dates::days_from_civil(2024, 1, 5));
+        let mut b = task("x marks +the @spot due:now pri:A", "home", "");
+        b.done = true;
+        b.priority = 3;
+        let tasks = vec![
+            a,
+            b,
+            task("(B) is not a priority", "work", "%20"),
+            task("2024-01-01 is not a date", "work", ""),
+            task("50% of %41", "a+b", "c@d"),
+        ];
+        let parsed = parse(&write(&tasks));
+        assert_eq!(parsed.len(), tasks.len());
+        for (parsed, task) in parsed.iter().zip(tasks.iter()) {
+            assert!(parsed.same_as(task), "{} came back as {}", task.name, parsed.name);
+        }
+    }
+
+    #[test]
+    fn other_tools_lines() {
+        let task = parse_line("x 2024-01-02 2024-01-01 Call mom +Family +Other @phone due:2024-01-05 pri:B").unwrap();
+        assert!(task.done);
+        assert_eq!(task.name, "Call mom +Other");
+        assert_eq!(task.category, "Family");
+        assert_eq!(task.sub_category, "phone");
+        assert_eq!(task.priority, 7);
+        assert_eq!(dates::due_days(&task.due), dates::days_from_civil(2024, 1, 5));
+        assert!(parse_line("   ").is_none());
+    }
+
+    #[test]
+    fn edited_file() {
+        let tasks = vec![task("a  spaced", "work", ""), task("b", "work", ""), task("c", "home", ""), task("d", "home", "")];
+        let written = write(&tasks);
+        let mut lines : Vec<String> = written.lines().map(|line| line.to_string()).collect();
+        // `a` done, `b` gone, `c` renamed, something new at the end and a blank line
+        lines[0] = format!("x {}", lines[0]);
+        lines.remove(1);
+        lines[1] = lines[1].replace('c', "see");
+        lines.push(String::new());
+        lines.push("e +home".to_string());
+
+        let edited = changes(&tasks, &lines.join("\n"));
+        assert_eq!(edited.changed.len(), 2);
+        assert_eq!(edited.changed[0].0, 0);
+        assert!(edited.changed[0].1.done);
+        // The spaces the line couldn't hold are kept
+        assert_eq!(edited.changed[0].1.name, "a  spaced");
+        assert_eq!(edited.changed[1].0, 2);
+        assert_eq!(edited.changed[1].1.name, "see");
+        assert_eq!(edited.removed, [1]);
+        assert_eq!(edited.added.len(), 1);
+        assert_eq!(edited.added[0].name, "e");
+
+        let unchanged = changes(&tasks, &written);
+        assert!(unchanged.changed.is_empty() && unchanged.removed.is_empty() && unchanged.added.is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+//! Undo/redo history of everything done to the `TasksManager` from the ui
+
+use cool_organizer::*;
+
+/// Copy of the fields of a task the ui can edit
+#[derive(Clone)]
+pub struct TaskData {
+    pub name : String,
+    pub category : String,
+    pub sub_category : String,
+    pub due : Option<Date>,
+    pub done : bool,
+    pub priority : u8,
+}
+impl TaskData {
+    pub fn from_task(task : &Task) -> TaskData {
+        TaskData {
+            name : task.name.clone(),
+            category : task.category.clone(),
+            sub_category : task.sub_category.clone(),
+            due : task.due,
+            done : task.done,
+            priority : task.priority,
+        }
+    }
+
+    pub fn apply(&self, task : &mut Task) {
+        task.name = self.name.clone();
+        task.category = self.category.clone();
+        task.sub_category = self.sub_category.clone();
+        task.due = self.due;
+        task.done = self.done;
+        task.priority = self.priority;
+    }
+
+    pub fn to_task(&self) -> Task {
+        let mut task = Task::new(&self.name);
+        self.apply(&mut task);
+        task
+    }
+
+    /// `Date` can't be compared directly, so compare the days instead
+    pub fn same_as(&self, other : &TaskData) -> bool {
+        self.name == other.name
+            && self.category == other.category
+            && self.sub_category == other.sub_category
+            && crate::dates::due_days(&self.due) == crate::dates::due_days(&other.due)
+            && self.done == other.done
+            && self.priority == other.priority
+    }
+}
+
+/// A single step in the history, indices are into `TasksManager::tasks`
+pub enum Edit {
+    /// The task's fields changed, moving it to another category included
+    Change { index : usize, before : TaskData, after : TaskData },
+    /// A task was added at `index`
+    Add { index : usize, task : TaskData },
+    /// The task at `index` was removed
+    Remove { index : usize, task : TaskData },
+    /// A bunch of tasks removed at once(Remove Done), sorted by the index they had before
+    RemoveMany { tasks : Vec<(usize, TaskData)> },
+    /// The task at `from` was taken out and put back at `to`(dragged around), changing on the way
+    Move { from : usize, to : usize, before : TaskData, after : TaskData },
+    /// Several edits made at once(bulk editing), done and undone together in this order
+    Batch { edits : Vec<Edit> },
+}
+impl Edit {
+    /// A single edit stays as it is, more become a `Batch`, `None` if there are none
+    pub fn batch(mut edits : Vec<Edit>) -> Option<Edit> {
+        match edits.len() {
+            0 => None,
+            1 => edits.pop(),
+            _ => Some(Edit::Batch { edits }),
+        }
+    }
+
+    /// Does the edit(again), returns the task worth selecting afterwards.
+    /// Indices which don't fit the tasks(the history and the list disagree) leave them alone
+    fn apply(&self, tasks : &mut TasksManager) -> Option<usize> {
+        let len = tasks.tasks.len();
+        match self {
+            Edit::Change { index, after, .. } => {
+                after.apply(tasks.tasks.get_mut(*index)?);
+                Some(*index)
+            }
+            Edit::Add { index, task } => {
+                if *index > len {
+                    return None;
+                }
+                tasks.tasks.insert(*index, task.to_task());
+                Some(*index)
+            }
+            Edit::Remove { index, .. } => {
+                if *index < len {
+                    tasks.tasks.remove(*index);
+                }
+                None
+            }
+            Edit::RemoveMany { tasks : removed } => {
+                if removed.iter().any(|(index, _)| *index >= len) {
+                    return None;
+                }
+                // Back to front so the indices before are still correct
+                for (index, _) in removed.iter().rev() {
+                    tasks.tasks.remove(*index);
+                }
+                None
+            }
+            Edit::Move { from, to, after, .. } => {
+                if *from >= len || *to >= len {
+                    return None;
+                }
+                let mut task = tasks.tasks.remove(*from);
+                after.apply(&mut task);
+                tasks.tasks.insert(*to, task);
+                Some(*to)
+            }
+            Edit::Batch { edits } => {
+                let mut select = None;
+                for edit in edits.iter() {
+                    select = edit.apply(tasks).or(select);
+                }
+                select
+            }
+        }
+    }
+
+    /// Takes the edit back, returns the task worth selecting afterwards. Out of range indices are ignored like in `apply`
+    fn revert(&self, tasks : &mut TasksManager) -> Option<usize> {
+        let len = tasks.tasks.len();
+        match self {
+            Edit::Change { index, before, .. } => {
+                before.apply(tasks.tasks.get_mut(*index)?);
+                Some(*index)
+            }
+            Edit::Add { index, .. } => {
+                if *index < len {
+                    tasks.tasks.remove(*index);
+                }
+                None
+            }
+            Edit::Remove { index, task } => {
+                if *index > len {
+                    return None;
+                }
+                tasks.tasks.insert(*index, task.to_task());
+                Some(*index)
+            }
+            Edit::RemoveMany { tasks : removed } => {
+                // Front to back, every insert makes room for the next
+                if removed.iter().enumerate().any(|(n, (index, _))| *index > len + n) {
+                    return None;
+                }
+                for (index, task) in removed.iter() {
+                    tasks.tasks.insert(*index, task.to_task());
+                }
+                removed.first().map(|(i, _)| *i)
+            }
+            Edit::Move { from, to, before, .. } => {
+                if *from >= len || *to >= len {
+                    return None;
+                }
+                let mut task = tasks.tasks.remove(*to);
+                before.apply(&mut task);
+                tasks.tasks.insert(*from, task);
+                Some(*from)
+            }
+            Edit::Batch { edits } => {
+                let mut select = None;
+                for edit in edits.iter().rev() {
+                    select = edit.revert(tasks).or(select);
+                }
+                select
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    undo : Vec<Edit>,
+    redo : Vec<Edit>,
+}
+impl History {
+    /// Records an edit which was already applied, anything undone before it is forgotten
+    pub fn push(&mut self, edit : Edit) {
+        self.undo.push(edit);
+        self.redo.clear();
+    }
+
+    /// Forgets everything, for when the edits no longer make sense(another file was opened)
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Reverts the last edit, `None` if there was nothing to undo,
+    /// otherwise the task worth selecting(if any)
+    pub fn undo(&mut self, tasks : &mut TasksManager) -> Option<Option<usize>> {
+        let edit = self.undo.pop()?;
+        let select = edit.revert(tasks);
+        self.redo.push(edit);
+        Some(select)
+    }
+
+    /// Applies the last undone edit again, same return value as `undo`
+    pub fn redo(&mut self, tasks : &mut TasksManager) -> Option<Option<usize>> {
+        let edit = self.redo.pop()?;
+        let select = edit.apply(tasks);
+        self.undo.push(edit);
+        Some(select)
+    }
+}